@@ -0,0 +1,102 @@
+use anyhow::{Result, bail};
+use pretty_jinja::config::FormatOptions;
+use serde_json::{Map, Value};
+use std::path::PathBuf;
+
+pub(crate) const HELP: &str = "\
+Format Jinja templates.
+
+Usage: pretty_jinja [OPTIONS] [PATH]...
+
+Files will be formatted in place. Directories will be searched recursively for
+`.j2`, `.jinja` and `.jinja2` files. Read from stdin and write to stdout if no
+paths are given or path is `-`.
+
+Options:
+  --check               Don't write files, but print diff of unformatted files
+                        and exit with non-zero code
+  --config <FILE>       Read format options from the given config file, instead
+                        of searching `.pretty_jinja.toml`, `.prettyjinjarc`,
+                        `.pretty_jinja.json` or `pyproject.toml`
+  --<OPTION> <VALUE>    Set format option, for example `--print-width 100`,
+                        `--use-tabs` or `--args.trailing-comma never`
+  -h, --help            Print help
+";
+
+#[derive(Debug, Default)]
+pub(crate) struct Args {
+    pub check: bool,
+    pub help: bool,
+    pub config: Option<PathBuf>,
+    /// Format options from command line, with keys in snake case.
+    pub options: Map<String, Value>,
+    pub paths: Vec<PathBuf>,
+}
+
+/// Parse command line arguments.
+///
+/// Known option names and their default values are needed to tell
+/// whether flags of boolean options are followed by values or not.
+pub(crate) fn parse_args(
+    args: impl IntoIterator<Item = String>,
+    known_options: &Map<String, Value>,
+) -> Result<Args> {
+    let mut parsed = Args::default();
+    let mut args = args.into_iter().peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--check" => parsed.check = true,
+            "-h" | "--help" => parsed.help = true,
+            "--config" => match args.next() {
+                Some(path) => parsed.config = Some(path.into()),
+                None => bail!("missing value for `--config`"),
+            },
+            "-" => parsed.paths.push(arg.into()),
+            _ => {
+                let Some(flag) = arg.strip_prefix("--") else {
+                    parsed.paths.push(arg.into());
+                    continue;
+                };
+                let (name, inline_value) = match flag.split_once('=') {
+                    Some((name, value)) => (name, Some(value.to_string())),
+                    None => (flag, None),
+                };
+                let key = name.replace('-', "_");
+                let Some(default) = known_options.get(&key) else {
+                    bail!("unknown option `--{name}`");
+                };
+                let is_boolean = default.is_boolean() || default.is_null() && accepts_boolean(&key);
+                let value = match inline_value {
+                    Some(value) => value,
+                    None => {
+                        let takes_value = match args.peek() {
+                            Some(next) if is_boolean => {
+                                matches!(next.as_str(), "true" | "false")
+                            }
+                            Some(next) => !next.starts_with("--"),
+                            None => false,
+                        };
+                        if takes_value {
+                            args.next().unwrap_or_default()
+                        } else if is_boolean {
+                            "true".into()
+                        } else {
+                            bail!("missing value for `--{name}`");
+                        }
+                    }
+                };
+                let value = serde_json::from_str(&value).unwrap_or(Value::String(value));
+                parsed.options.insert(key, value);
+            }
+        }
+    }
+    Ok(parsed)
+}
+
+/// Some options are `null` by default, like `args.prefer_single_line` which accepts boolean,
+/// while others like `args.trailing_comma` require values.
+fn accepts_boolean(key: &str) -> bool {
+    let mut options = Map::new();
+    options.insert(key.into(), true.into());
+    serde_json::from_value::<FormatOptions>(Value::Object(options)).is_ok()
+}
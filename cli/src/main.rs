@@ -0,0 +1,150 @@
+use crate::args::{HELP, parse_args};
+use anyhow::{Context, Result};
+use pretty_jinja::{
+    config::FormatOptions,
+    config_file::{ConfigFile, normalize_keys},
+    format_template,
+};
+use serde_json::Value;
+use similar::TextDiff;
+use std::{
+    env, fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+mod args;
+
+const EXTENSIONS: [&str; 3] = ["j2", "jinja", "jinja2"];
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(error) => {
+            eprintln!("error: {error:#}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Returns `false` if there're unformatted files in check mode.
+fn run() -> Result<bool> {
+    let known_options = match serde_json::to_value(FormatOptions::default())? {
+        Value::Object(map) => map,
+        _ => unreachable!("format options should be serialized as object"),
+    };
+    let args = parse_args(env::args().skip(1), &known_options)?;
+    if args.help {
+        print!("{HELP}");
+        return Ok(true);
+    }
+
+    let config_file = match &args.config {
+        Some(path) => Some(
+            ConfigFile::load(path)
+                .map_err(anyhow::Error::msg)?
+                .with_context(|| format!("no options found in `{}`", path.display()))?,
+        ),
+        None => None,
+    };
+    let resolve_options = |file: &Path| -> Result<FormatOptions> {
+        let found;
+        let config_file = match &config_file {
+            Some(config_file) => Some(config_file),
+            None => {
+                found = ConfigFile::find(file).map_err(anyhow::Error::msg)?;
+                found.as_ref()
+            }
+        };
+        // one option can be spelled in both camel case and snake case,
+        // so keys must be normalized before merging, otherwise it will be a duplicated field
+        let mut options = config_file
+            .map(|config_file| normalize_keys(config_file.options_for(file)))
+            .unwrap_or_default();
+        options.extend(normalize_keys(args.options.clone()));
+        serde_json::from_value(Value::Object(options)).context("invalid format options")
+    };
+
+    if args.paths.is_empty() || args.paths.iter().any(|path| path == Path::new("-")) {
+        let options = resolve_options(&env::current_dir()?.join("<stdin>"))?;
+        let mut code = String::new();
+        io::stdin().read_to_string(&mut code)?;
+        let formatted = format_template(&code, &options).map_err(anyhow::Error::msg)?;
+        if args.check {
+            if formatted != code {
+                print_diff("<stdin>", &code, &formatted)?;
+                return Ok(false);
+            }
+        } else {
+            io::stdout().write_all(formatted.as_bytes())?;
+        }
+        return Ok(true);
+    }
+
+    let mut files = Vec::new();
+    for path in &args.paths {
+        if path.is_dir() {
+            collect_files(path, &mut files)?;
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    let mut all_formatted = true;
+    let mut has_error = false;
+    for file in files {
+        let code = fs::read_to_string(&file)
+            .with_context(|| format!("failed to read `{}`", file.display()))?;
+        let options = resolve_options(&file)?;
+        let formatted = match format_template(&code, &options) {
+            Ok(formatted) => formatted,
+            Err(error) => {
+                eprintln!("error: {}: {error}", file.display());
+                has_error = true;
+                continue;
+            }
+        };
+        if formatted == code {
+            continue;
+        }
+        if args.check {
+            all_formatted = false;
+            print_diff(&file.display().to_string(), &code, &formatted)?;
+        } else {
+            fs::write(&file, formatted)
+                .with_context(|| format!("failed to write `{}`", file.display()))?;
+        }
+    }
+    if has_error {
+        anyhow::bail!("some files can't be formatted");
+    }
+    Ok(all_formatted)
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let mut entries = fs::read_dir(dir)
+        .with_context(|| format!("failed to read directory `{}`", dir.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else if path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| EXTENSIONS.contains(&ext))
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn print_diff(name: &str, old: &str, new: &str) -> io::Result<()> {
+    let diff = TextDiff::from_lines(old, new);
+    let mut stdout = io::stdout().lock();
+    write!(stdout, "{}", diff.unified_diff().header(name, name))
+}
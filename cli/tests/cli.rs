@@ -0,0 +1,148 @@
+use similar_asserts::assert_eq;
+use std::{
+    fs,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Output, Stdio},
+};
+
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_pretty_jinja"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("pretty_jinja_cli_{name}"));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn stdin() {
+    let output = run(&["--kwarg-eq-spacing"], "{%if  a%}{{foo(b=1)}}{%endif%}\n");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{% if a %}{{ foo(b = 1) }}{% endif %}\n"
+    );
+}
+
+#[test]
+fn check_stdin() {
+    let output = run(&["--check"], "{{a+1}}\n");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "--- <stdin>\n+++ <stdin>\n@@ -1 +1 @@\n-{{a+1}}\n+{{ a + 1 }}\n"
+    );
+
+    let output = run(&["--check"], "{{ a + 1 }}\n");
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn format_files_in_place() {
+    let dir = temp_dir("in_place");
+    fs::create_dir(dir.join("nested")).unwrap();
+    fs::write(dir.join("a.html.j2"), "{{a+1}}").unwrap();
+    fs::write(dir.join("nested/b.jinja"), "{%set  x=1%}").unwrap();
+    fs::write(dir.join("c.txt"), "{{a+1}}").unwrap();
+    fs::write(dir.join("config.json"), r#"{ "bracketSpacing": true }"#).unwrap();
+    fs::write(dir.join("d.jinja2"), "{{[1,2]}}").unwrap();
+
+    let output = run(
+        &[
+            "--config",
+            dir.join("config.json").to_str().unwrap(),
+            dir.to_str().unwrap(),
+        ],
+        "",
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(dir.join("a.html.j2")).unwrap(),
+        "{{ a + 1 }}"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.join("nested/b.jinja")).unwrap(),
+        "{% set x = 1 %}"
+    );
+    assert_eq!(fs::read_to_string(dir.join("c.txt")).unwrap(), "{{a+1}}");
+    assert_eq!(
+        fs::read_to_string(dir.join("d.jinja2")).unwrap(),
+        "{{ [ 1, 2 ] }}"
+    );
+}
+
+#[test]
+fn unknown_option() {
+    let output = run(&["--unknown-option", "1"], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "error: unknown option `--unknown-option`\n"
+    );
+}
+
+#[test]
+fn discover_config_file() {
+    let dir = temp_dir("discover");
+    fs::create_dir(dir.join("templates")).unwrap();
+    fs::write(dir.join(".pretty_jinja.toml"), "brace_spacing = true\n").unwrap();
+    fs::write(dir.join("templates/a.j2"), "{{{'a':1}}}").unwrap();
+
+    let output = run(&[dir.join("templates").to_str().unwrap()], "");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(dir.join("templates/a.j2")).unwrap(),
+        "{{ { 'a': 1 } }}"
+    );
+}
+
+#[test]
+fn mixed_option_spellings() {
+    let dir = temp_dir("mixed_option_spellings");
+    fs::write(dir.join(".pretty_jinja.json"), r#"{ "printWidth": 20 }"#).unwrap();
+    fs::write(dir.join("a.j2"), "{{ foo(aaaaaaaaaa, bbbbbbbbbb) }}").unwrap();
+
+    let output = run(
+        &["--print-width", "100", dir.join("a.j2").to_str().unwrap()],
+        "",
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(dir.join("a.j2")).unwrap(),
+        "{{ foo(aaaaaaaaaa, bbbbbbbbbb) }}"
+    );
+}
+
+#[test]
+fn missing_option_value() {
+    let output = run(&["--args.trailing-comma"], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "error: missing value for `--args.trailing-comma`\n"
+    );
+
+    let output = run(
+        &["--args.prefer-single-line", "--use-tabs"],
+        "{{ foo(\na, b) }}",
+    );
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "{{ foo(a, b) }}");
+}
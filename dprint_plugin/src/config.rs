@@ -1,238 +1,117 @@
 use dprint_core::{
     configuration::{
         ConfigKeyMap, ConfigurationDiagnostic, GlobalConfiguration, NewLineKind,
-        get_nullable_value, get_unknown_property_diagnostics, get_value,
+        get_unknown_property_diagnostics,
     },
     plugins::{FileMatchingInfo, PluginResolveConfigurationResult},
 };
-use pretty_jinja::config::*;
+use pretty_jinja::config::FormatOptions;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
 
+/// Resolved plugin config.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Configuration {
+    #[serde(flatten)]
+    pub options: FormatOptions,
+    /// Whether `dialect` is set in `dprint.json`.
+    /// If not, dialect will be inferred from file extension.
+    #[serde(skip)]
+    pub dialect_set: bool,
+}
+
+/// Resolve plugin config by deserializing it through serde derives of [`FormatOptions`],
+/// so options added there are available here automatically.
+///
+/// Keys in `dprint.json` are option names in camel case, such as `exprDict.trailingComma`.
 pub(crate) fn resolve_config(
-    mut config: ConfigKeyMap,
+    config: ConfigKeyMap,
     global_config: &GlobalConfiguration,
-) -> PluginResolveConfigurationResult<FormatOptions> {
+) -> PluginResolveConfigurationResult<Configuration> {
     let mut diagnostics = Vec::new();
-    let pretty_jinja_config = FormatOptions {
-        layout: LayoutOptions {
-            print_width: get_value(
-                &mut config,
-                "printWidth",
-                global_config.line_width.unwrap_or(80),
-                &mut diagnostics,
-            ) as usize,
-            use_tabs: get_value(
-                &mut config,
-                "useTabs",
-                global_config.use_tabs.unwrap_or_default(),
-                &mut diagnostics,
-            ),
-            indent_width: get_value(
-                &mut config,
-                "indentWidth",
-                global_config.indent_width.unwrap_or(2),
-                &mut diagnostics,
-            ) as usize,
-            line_break: match &*get_value(
-                &mut config,
-                "lineBreak",
-                match global_config.new_line_kind {
-                    Some(NewLineKind::LineFeed) => "lf",
-                    Some(NewLineKind::CarriageReturnLineFeed) => "crlf",
-                    _ => "lf",
-                }
-                .to_string(),
-                &mut diagnostics,
-            ) {
-                "lf" => LineBreak::Lf,
-                "crlf" => LineBreak::Crlf,
-                _ => {
-                    diagnostics.push(ConfigurationDiagnostic {
-                        property_name: "lineBreak".into(),
-                        message: "invalid value for config `lineBreak`".into(),
-                    });
-                    LineBreak::Lf
-                }
-            },
-        },
-        language: LanguageOptions {
-            operator_linebreak: match &*get_value(
-                &mut config,
-                "operatorLinebreak",
-                "after".to_string(),
-                &mut diagnostics,
-            ) {
-                "before" => OperatorLineBreak::Before,
-                "after" => OperatorLineBreak::After,
-                _ => {
-                    diagnostics.push(ConfigurationDiagnostic {
-                        property_name: "operatorLinebreak".into(),
-                        message: "invalid value for config `operatorLinebreak`".into(),
-                    });
-                    Default::default()
-                }
-            },
-            trailing_comma: match &*get_value(
-                &mut config,
-                "trailingComma",
-                "only-multi-line".to_string(),
-                &mut diagnostics,
-            ) {
-                "never" => TrailingComma::Never,
-                "always" => TrailingComma::Always,
-                "only-multi-line" => TrailingComma::OnlyMultiLine,
-                _ => {
-                    diagnostics.push(ConfigurationDiagnostic {
-                        property_name: "trailingComma".into(),
-                        message: "invalid value for config `trailingComma`".into(),
-                    });
-                    Default::default()
-                }
-            },
-            args_trailing_comma: get_nullable_value::<String>(
-                &mut config,
-                "args.trailingComma",
-                &mut diagnostics,
-            )
-            .as_deref()
-            .and_then(|option_value| match option_value {
-                "never" => Some(TrailingComma::Never),
-                "always" => Some(TrailingComma::Always),
-                "only-multi-line" => Some(TrailingComma::OnlyMultiLine),
-                _ => {
-                    diagnostics.push(ConfigurationDiagnostic {
-                        property_name: "args.trailingComma".into(),
-                        message: "invalid value for config `args.trailingComma`".into(),
-                    });
-                    Default::default()
-                }
-            }),
-            expr_dict_trailing_comma: get_nullable_value::<String>(
-                &mut config,
-                "exprDict.trailingComma",
-                &mut diagnostics,
-            )
-            .as_deref()
-            .and_then(|option_value| match option_value {
-                "never" => Some(TrailingComma::Never),
-                "always" => Some(TrailingComma::Always),
-                "only-multi-line" => Some(TrailingComma::OnlyMultiLine),
-                _ => {
-                    diagnostics.push(ConfigurationDiagnostic {
-                        property_name: "exprDict.trailingComma".into(),
-                        message: "invalid value for config `exprDict.trailingComma`".into(),
-                    });
-                    Default::default()
-                }
-            }),
-            expr_list_trailing_comma: get_nullable_value::<String>(
-                &mut config,
-                "exprList.trailingComma",
-                &mut diagnostics,
-            )
-            .as_deref()
-            .and_then(|option_value| match option_value {
-                "never" => Some(TrailingComma::Never),
-                "always" => Some(TrailingComma::Always),
-                "only-multi-line" => Some(TrailingComma::OnlyMultiLine),
-                _ => {
-                    diagnostics.push(ConfigurationDiagnostic {
-                        property_name: "exprList.trailingComma".into(),
-                        message: "invalid value for config `exprList.trailingComma`".into(),
-                    });
-                    Default::default()
-                }
-            }),
-            expr_tuple_trailing_comma: get_nullable_value::<String>(
-                &mut config,
-                "exprTuple.trailingComma",
-                &mut diagnostics,
-            )
-            .as_deref()
-            .and_then(|option_value| match option_value {
-                "never" => Some(TrailingComma::Never),
-                "always" => Some(TrailingComma::Always),
-                "only-multi-line" => Some(TrailingComma::OnlyMultiLine),
-                _ => {
-                    diagnostics.push(ConfigurationDiagnostic {
-                        property_name: "exprTuple.trailingComma".into(),
-                        message: "invalid value for config `exprTuple.trailingComma`".into(),
-                    });
-                    Default::default()
-                }
-            }),
-            params_trailing_comma: get_nullable_value::<String>(
-                &mut config,
-                "params.trailingComma",
-                &mut diagnostics,
-            )
-            .as_deref()
-            .and_then(|option_value| match option_value {
-                "never" => Some(TrailingComma::Never),
-                "always" => Some(TrailingComma::Always),
-                "only-multi-line" => Some(TrailingComma::OnlyMultiLine),
-                _ => {
-                    diagnostics.push(ConfigurationDiagnostic {
-                        property_name: "params.trailingComma".into(),
-                        message: "invalid value for config `params.trailingComma`".into(),
-                    });
-                    Default::default()
-                }
-            }),
-            prefer_single_line: get_value(&mut config, "preferSingleLine", false, &mut diagnostics),
-            args_prefer_single_line: get_nullable_value(
-                &mut config,
-                "args.preferSingleLine",
-                &mut diagnostics,
-            ),
-            expr_dict_prefer_single_line: get_nullable_value(
-                &mut config,
-                "exprDict.preferSingleLine",
-                &mut diagnostics,
-            ),
-            expr_list_prefer_single_line: get_nullable_value(
-                &mut config,
-                "exprList.preferSingleLine",
-                &mut diagnostics,
-            ),
-            expr_tuple_prefer_single_line: get_nullable_value(
-                &mut config,
-                "exprTuple.preferSingleLine",
-                &mut diagnostics,
-            ),
-            params_prefer_single_line: get_nullable_value(
-                &mut config,
-                "params.preferSingleLine",
-                &mut diagnostics,
-            ),
-            brace_spacing: get_value(&mut config, "braceSpacing", false, &mut diagnostics),
-            bracket_spacing: get_value(&mut config, "bracketSpacing", false, &mut diagnostics),
-            args_paren_spacing: get_value(&mut config, "argsParenSpacing", false, &mut diagnostics),
-            params_paren_spacing: get_value(
-                &mut config,
-                "paramsParenSpacing",
-                false,
-                &mut diagnostics,
-            ),
-            tuple_paren_spacing: get_value(
-                &mut config,
-                "tupleParenSpacing",
-                false,
-                &mut diagnostics,
-            ),
-        },
-    };
+    let option_names = get_option_names();
 
-    diagnostics.extend(get_unknown_property_diagnostics(config));
+    let mut options = Map::new();
+    if let Some(line_width) = global_config.line_width {
+        options.insert("print_width".into(), line_width.into());
+    }
+    if let Some(use_tabs) = global_config.use_tabs {
+        options.insert("use_tabs".into(), use_tabs.into());
+    }
+    if let Some(indent_width) = global_config.indent_width {
+        options.insert("indent_width".into(), indent_width.into());
+    }
+    if let Some(NewLineKind::CarriageReturnLineFeed) = global_config.new_line_kind {
+        options.insert("line_break".into(), "crlf".into());
+    }
+
+    let dialect_set = config.contains_key("dialect");
+    let mut unknown = ConfigKeyMap::new();
+    for (key, value) in config {
+        let Some(name) = option_names.get(&key) else {
+            unknown.insert(key, value);
+            continue;
+        };
+        // check each option separately, so an invalid option doesn't affect others
+        let value = serde_json::to_value(value).unwrap_or_default();
+        let option = Map::from_iter([(name.clone(), value.clone())]);
+        if serde_json::from_value::<FormatOptions>(Value::Object(option)).is_ok() {
+            options.insert(name.clone(), value);
+        } else {
+            diagnostics.push(ConfigurationDiagnostic {
+                property_name: key.clone(),
+                message: format!("invalid value for config `{key}`"),
+            });
+        }
+    }
+    let pretty_jinja_config = serde_json::from_value(Value::Object(options)).unwrap_or_default();
+
+    diagnostics.extend(get_unknown_property_diagnostics(unknown));
 
     PluginResolveConfigurationResult {
-        config: pretty_jinja_config,
+        config: Configuration {
+            options: pretty_jinja_config,
+            dialect_set,
+        },
         diagnostics,
         file_matching: FileMatchingInfo {
-            file_extensions: vec![
-                "markup-fmt-jinja-expr".into(),
-                "markup-fmt-jinja-stmt".into(),
-            ],
+            file_extensions: ["markup-fmt-jinja-expr", "markup-fmt-jinja-stmt"]
+                .into_iter()
+                .chain(crate::template::EXTENSIONS)
+                .map(String::from)
+                .collect(),
             file_names: vec![],
         },
     }
 }
+
+/// Map from keys in `dprint.json` to option names of serde.
+fn get_option_names() -> HashMap<String, String> {
+    let Ok(Value::Object(options)) = serde_json::to_value(FormatOptions::default()) else {
+        return HashMap::new();
+    };
+    options
+        .into_iter()
+        .map(|(name, _)| (to_config_key(&name), name))
+        .collect()
+}
+
+/// Convert option name like `expr_dict.trailing_comma` to `exprDict.trailingComma`.
+fn to_config_key(name: &str) -> String {
+    name.split('.')
+        .map(|segment| {
+            let mut words = segment.split('_');
+            let first = words.next().unwrap_or_default().to_string();
+            words.fold(first, |mut key, word| {
+                let mut chars = word.chars();
+                if let Some(c) = chars.next() {
+                    key.extend(c.to_uppercase());
+                    key.push_str(chars.as_str());
+                }
+                key
+            })
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
@@ -8,13 +8,17 @@ use dprint_core::{
         SyncPluginHandler,
     },
 };
-use pretty_jinja::{config::FormatOptions, format_expr, format_stmt};
+use pretty_jinja::{format_expr, format_stmt};
 
 mod config;
+mod migration;
+mod template;
+
+pub use crate::config::Configuration;
 
 pub struct PrettyJinjaPluginHandler;
 
-impl SyncPluginHandler<FormatOptions> for PrettyJinjaPluginHandler {
+impl SyncPluginHandler<Configuration> for PrettyJinjaPluginHandler {
     fn plugin_info(&mut self) -> PluginInfo {
         let version = env!("CARGO_PKG_VERSION").to_string();
         PluginInfo {
@@ -37,30 +41,41 @@ impl SyncPluginHandler<FormatOptions> for PrettyJinjaPluginHandler {
         &mut self,
         config: ConfigKeyMap,
         global_config: &GlobalConfiguration,
-    ) -> PluginResolveConfigurationResult<FormatOptions> {
+    ) -> PluginResolveConfigurationResult<Configuration> {
         resolve_config(config, global_config)
     }
 
-    fn check_config_updates(&self, _: CheckConfigUpdatesMessage) -> Result<Vec<ConfigChange>> {
-        Ok(Vec::new())
+    fn check_config_updates(
+        &self,
+        message: CheckConfigUpdatesMessage,
+    ) -> Result<Vec<ConfigChange>> {
+        Ok(migration::check_config_updates(message))
     }
 
     fn format(
         &mut self,
-        request: SyncFormatRequest<FormatOptions>,
-        _: impl FnMut(SyncHostFormatRequest) -> FormatResult,
+        request: SyncFormatRequest<Configuration>,
+        format_with_host: impl FnMut(SyncHostFormatRequest) -> FormatResult,
     ) -> FormatResult {
         match request.file_path.extension().and_then(|s| s.to_str()) {
-            Some("markup-fmt-jinja-expr") => {
-                format_expr(std::str::from_utf8(&request.file_bytes)?, request.config)
-                    .map(|output| Some(output.into_bytes()))
-                    .map_err(|error| anyhow::anyhow!(error))
-            }
-            Some("markup-fmt-jinja-stmt") => {
-                format_stmt(std::str::from_utf8(&request.file_bytes)?, request.config)
-                    .map(|output| Some(output.into_bytes()))
-                    .map_err(|error| anyhow::anyhow!(error))
-            }
+            Some("markup-fmt-jinja-expr") => format_expr(
+                std::str::from_utf8(&request.file_bytes)?,
+                &request.config.options,
+            )
+            .map(|output| Some(output.into_bytes()))
+            .map_err(|error| anyhow::anyhow!(error)),
+            Some("markup-fmt-jinja-stmt") => format_stmt(
+                std::str::from_utf8(&request.file_bytes)?,
+                &request.config.options,
+            )
+            .map(|output| Some(output.into_bytes()))
+            .map_err(|error| anyhow::anyhow!(error)),
+            Some(extension) if template::EXTENSIONS.contains(&extension) => template::format(
+                request.file_path,
+                std::str::from_utf8(&request.file_bytes)?,
+                request.config,
+                format_with_host,
+            ),
             _ => Ok(None),
         }
     }
@@ -70,5 +85,5 @@ impl SyncPluginHandler<FormatOptions> for PrettyJinjaPluginHandler {
 dprint_core::generate_plugin_code!(
     PrettyJinjaPluginHandler,
     PrettyJinjaPluginHandler,
-    FormatOptions
+    Configuration
 );
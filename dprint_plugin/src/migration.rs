@@ -0,0 +1,82 @@
+use dprint_core::{
+    configuration::ConfigKeyMap,
+    plugins::{CheckConfigUpdatesMessage, ConfigChange, ConfigChangeKind},
+};
+
+/// Migration of config which was introduced in specific plugin version.
+struct Migration {
+    /// Plugin version that introduced this migration.
+    /// It's applied only when upgrading from a version lower than this.
+    version: (u32, u32, u32),
+    migrate: fn(&mut ConfigKeyMap),
+}
+
+/// Migrations in the order of versions.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        // this key was documented in schema, but never accepted by plugin
+        version: (0, 2, 1),
+        migrate: |config| rename_key(config, "operatorLineBreak", "operatorLinebreak"),
+    },
+    Migration {
+        // this key is accepted by config files of command line, but not by plugin
+        version: (0, 2, 1),
+        migrate: |config| rename_key(config, "linebreak", "lineBreak"),
+    },
+];
+
+pub(crate) fn check_config_updates(message: CheckConfigUpdatesMessage) -> Vec<ConfigChange> {
+    // versions of dprint before 0.47 don't send old version,
+    // then all migrations are applied, which do nothing if config is up to date
+    let old_version = message.old_version.as_deref().and_then(parse_version);
+
+    let mut config = message.config.clone();
+    MIGRATIONS
+        .iter()
+        .filter(|migration| old_version.is_none_or(|old_version| old_version < migration.version))
+        .for_each(|migration| (migration.migrate)(&mut config));
+
+    diff_config(&message.config, &config)
+}
+
+fn parse_version(version: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = version.split(['.', '-', '+']);
+    Some((
+        parts.next()?.parse().ok()?,
+        parts.next()?.parse().ok()?,
+        parts.next()?.parse().ok()?,
+    ))
+}
+
+/// Rename key of config while keeping its value.
+/// If the new key already exists, the old key will be removed.
+fn rename_key(config: &mut ConfigKeyMap, old: &str, new: &str) {
+    if let Some(value) = config.shift_remove(old)
+        && !config.contains_key(new)
+    {
+        config.insert(new.to_string(), value);
+    }
+}
+
+/// Changes which convert old config to new config.
+fn diff_config(old: &ConfigKeyMap, new: &ConfigKeyMap) -> Vec<ConfigChange> {
+    let removed = old
+        .keys()
+        .filter(|key| !new.contains_key(*key))
+        .map(|key| ConfigChange {
+            path: vec![key.clone().into()],
+            kind: ConfigChangeKind::Remove,
+        });
+    let updated = new.iter().filter_map(|(key, value)| {
+        let kind = match old.get(key) {
+            Some(old_value) if old_value == value => return None,
+            Some(_) => ConfigChangeKind::Set(value.clone()),
+            None => ConfigChangeKind::Add(value.clone()),
+        };
+        Some(ConfigChange {
+            path: vec![key.clone().into()],
+            kind,
+        })
+    });
+    removed.chain(updated).collect()
+}
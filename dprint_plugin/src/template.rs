@@ -0,0 +1,114 @@
+use crate::config::Configuration;
+use dprint_core::{
+    configuration::ConfigKeyMap,
+    plugins::{FormatResult, SyncHostFormatRequest},
+};
+use pretty_jinja::config::{Dialect, FormatOptions};
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+};
+
+/// Extensions of Jinja template files.
+pub(crate) const EXTENSIONS: [&str; 4] = ["j2", "jinja", "jinja2", "tera"];
+
+/// Format the whole template file.
+///
+/// For templates like `config.yaml.j2`, the text around Jinja tags will be formatted
+/// by other plugins as `config.yaml`, with Jinja tags replaced by placeholders.
+pub(crate) fn format(
+    file_path: &Path,
+    code: &str,
+    config: &Configuration,
+    format_with_host: impl FnMut(SyncHostFormatRequest) -> FormatResult,
+) -> FormatResult {
+    let options = &get_options(file_path, config);
+    let mut output =
+        pretty_jinja::format_template(code, options).map_err(|error| anyhow::anyhow!(error))?;
+
+    if let Some(inner_path) = get_inner_file_path(file_path)
+        && let Some(formatted) =
+            format_text_with_host(&inner_path, &output, options, format_with_host)
+    {
+        output = formatted;
+    }
+
+    Ok((output != code).then(|| output.into_bytes()))
+}
+
+/// Options for the given file, with dialect inferred from file extension
+/// if it isn't set in config.
+fn get_options<'a>(file_path: &Path, config: &'a Configuration) -> Cow<'a, FormatOptions> {
+    if !config.dialect_set && file_path.extension().is_some_and(|ext| ext == "tera") {
+        let mut options = config.options.clone();
+        options.language.dialect = Dialect::Tera;
+        Cow::Owned(options)
+    } else {
+        Cow::Borrowed(&config.options)
+    }
+}
+
+/// Format text of template by host with Jinja tags replaced by placeholders,
+/// then put tags back.
+///
+/// It returns `None` if host doesn't change anything, fails to format the text
+/// (placeholders may not be valid in that language), or breaks placeholders.
+fn format_text_with_host(
+    inner_path: &Path,
+    code: &str,
+    options: &FormatOptions,
+    mut format_with_host: impl FnMut(SyncHostFormatRequest) -> FormatResult,
+) -> Option<String> {
+    let tags = pretty_jinja::scan_template_tags(code, options.language.dialect).ok()?;
+    let prefix = get_placeholder_prefix(code);
+
+    let mut text = String::with_capacity(code.len());
+    let mut pos = 0;
+    tags.iter().enumerate().for_each(|(i, range)| {
+        text.push_str(&code[pos..usize::from(range.start())]);
+        text.push_str(&format!("{prefix}{i}_"));
+        pos = range.end().into();
+    });
+    text.push_str(&code[pos..]);
+
+    let formatted = format_with_host(SyncHostFormatRequest {
+        file_path: inner_path,
+        file_bytes: text.as_bytes(),
+        range: None,
+        override_config: &ConfigKeyMap::new(),
+    })
+    .ok()??;
+    let formatted = String::from_utf8(formatted).ok()?;
+
+    let mut output = String::with_capacity(formatted.len());
+    let mut pos = 0;
+    for (i, range) in tags.iter().enumerate() {
+        let placeholder = format!("{prefix}{i}_");
+        let offset = pos + formatted[pos..].find(&placeholder)?;
+        output.push_str(&formatted[pos..offset]);
+        output.push_str(&code[*range]);
+        pos = offset + placeholder.len();
+    }
+    // placeholders are duplicated or out of order
+    if formatted[pos..].contains(&prefix) {
+        return None;
+    }
+    output.push_str(&formatted[pos..]);
+    Some(output)
+}
+
+/// Prefix of placeholders which doesn't appear in the given code.
+fn get_placeholder_prefix(code: &str) -> String {
+    let mut prefix = "jinja".to_string();
+    while code.contains(&prefix) {
+        prefix.push('_');
+    }
+    prefix
+}
+
+/// Path of file that template renders to, such as `config.yaml` for `config.yaml.j2`.
+fn get_inner_file_path(file_path: &Path) -> Option<PathBuf> {
+    let stem = Path::new(file_path.file_stem()?);
+    stem.extension()?;
+    Some(file_path.with_file_name(stem))
+}
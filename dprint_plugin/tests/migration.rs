@@ -0,0 +1,97 @@
+use dprint_core::{
+    configuration::{ConfigKeyMap, ConfigKeyValue},
+    plugins::{CheckConfigUpdatesMessage, ConfigChange, ConfigChangeKind, SyncPluginHandler},
+};
+use dprint_plugin_jinja::PrettyJinjaPluginHandler;
+use similar_asserts::assert_eq;
+
+fn check(old_version: Option<&str>, config: &[(&str, ConfigKeyValue)]) -> Vec<String> {
+    let config = config
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.clone()))
+        .collect::<ConfigKeyMap>();
+    PrettyJinjaPluginHandler
+        .check_config_updates(CheckConfigUpdatesMessage {
+            old_version: old_version.map(String::from),
+            config,
+        })
+        .unwrap()
+        .iter()
+        .map(summarize)
+        .collect()
+}
+
+fn summarize(change: &ConfigChange) -> String {
+    let path = serde_json::to_string(&change.path).unwrap();
+    match &change.kind {
+        ConfigChangeKind::Add(value) => format!("add {path} {value:?}"),
+        ConfigChangeKind::Set(value) => format!("set {path} {value:?}"),
+        ConfigChangeKind::Remove => format!("remove {path}"),
+    }
+}
+
+#[test]
+fn operator_line_break() {
+    assert_eq!(
+        check(
+            Some("0.2.0"),
+            &[
+                ("printWidth", ConfigKeyValue::Number(100)),
+                ("operatorLineBreak", "before".to_string().into()),
+            ]
+        ),
+        [
+            r#"remove ["operatorLineBreak"]"#,
+            r#"add ["operatorLinebreak"] String("before")"#,
+        ]
+    );
+}
+
+#[test]
+fn line_break() {
+    assert_eq!(
+        check(Some("0.2.0"), &[("linebreak", "crlf".to_string().into())]),
+        [
+            r#"remove ["linebreak"]"#,
+            r#"add ["lineBreak"] String("crlf")"#,
+        ]
+    );
+
+    // keep the new key if both exist
+    assert_eq!(
+        check(
+            Some("0.2.0"),
+            &[
+                ("lineBreak", "lf".to_string().into()),
+                ("linebreak", "crlf".to_string().into()),
+            ]
+        ),
+        [r#"remove ["linebreak"]"#]
+    );
+}
+
+#[test]
+fn skip_migrations_of_older_versions() {
+    let config = [("operatorLineBreak", "before".to_string().into())];
+    assert_eq!(check(Some("0.2.1"), &config).len(), 0);
+    assert_eq!(check(Some("0.3.0-beta.1"), &config).len(), 0);
+    assert_eq!(check(Some("0.1.5"), &config).len(), 2);
+    // old version is unknown
+    assert_eq!(check(None, &config).len(), 2);
+    assert_eq!(check(Some("latest"), &config).len(), 2);
+}
+
+#[test]
+fn up_to_date() {
+    assert_eq!(
+        check(
+            None,
+            &[
+                ("operatorLinebreak", "before".to_string().into()),
+                ("exprDict.trailingComma", "never".to_string().into()),
+            ]
+        )
+        .len(),
+        0
+    );
+}
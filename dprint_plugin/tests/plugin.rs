@@ -0,0 +1,217 @@
+use dprint_core::{
+    configuration::{ConfigKeyMap, ConfigKeyValue, GlobalConfiguration, NewLineKind},
+    plugins::{
+        FormatConfigId, FormatResult, NullCancellationToken, SyncFormatRequest,
+        SyncHostFormatRequest, SyncPluginHandler,
+    },
+};
+use dprint_plugin_jinja::{Configuration, PrettyJinjaPluginHandler};
+use pretty_jinja::config::{FormatOptions, LineBreak, OperatorLineBreak, TrailingComma};
+use similar_asserts::assert_eq;
+use std::path::{Path, PathBuf};
+
+fn format(
+    file_path: &str,
+    code: &str,
+    format_with_host: impl FnMut(SyncHostFormatRequest) -> FormatResult,
+) -> Option<String> {
+    format_with_config(file_path, code, &Configuration::default(), format_with_host)
+}
+
+fn format_with_config(
+    file_path: &str,
+    code: &str,
+    config: &Configuration,
+    format_with_host: impl FnMut(SyncHostFormatRequest) -> FormatResult,
+) -> Option<String> {
+    PrettyJinjaPluginHandler
+        .format(
+            SyncFormatRequest {
+                file_path: Path::new(file_path),
+                file_bytes: code.as_bytes().to_vec(),
+                config_id: FormatConfigId::from_raw(1),
+                config,
+                range: None,
+                token: &NullCancellationToken,
+            },
+            format_with_host,
+        )
+        .unwrap()
+        .map(|bytes| String::from_utf8(bytes).unwrap())
+}
+
+#[test]
+fn file_extensions() {
+    let result = PrettyJinjaPluginHandler
+        .resolve_config(ConfigKeyMap::new(), &GlobalConfiguration::default());
+    assert_eq!(
+        result.file_matching.file_extensions,
+        [
+            "markup-fmt-jinja-expr",
+            "markup-fmt-jinja-stmt",
+            "j2",
+            "jinja",
+            "jinja2",
+            "tera"
+        ]
+    );
+}
+
+#[test]
+fn template_file() {
+    let output = format("page.jinja", "<p>{{a+1}}</p>\n", |_| {
+        panic!("host shouldn't be called")
+    });
+    assert_eq!(output.as_deref(), Some("<p>{{ a + 1 }}</p>\n"));
+
+    let output = format("page.html.j2", "<p>{{ a }}</p>\n", |_| Ok(None));
+    assert_eq!(output, None);
+}
+
+#[test]
+fn tera_file() {
+    let code = "{{macros::input()}}\n";
+    let output = format("page.tera", code, |_| panic!("host shouldn't be called"));
+    assert_eq!(output.as_deref(), Some("{{ macros::input() }}\n"));
+
+    // dialect set explicitly isn't overridden by file extension,
+    // so the tag can't be parsed and is kept as-is
+    let config = [("dialect", "jinja".to_string().into())]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect();
+    let config = PrettyJinjaPluginHandler
+        .resolve_config(config, &GlobalConfiguration::default())
+        .config;
+    assert!(config.dialect_set);
+    let output = format_with_config("page.tera", code, &config, |_| {
+        panic!("host shouldn't be called")
+    });
+    assert_eq!(output, None);
+}
+
+#[test]
+fn template_file_without_tags() {
+    let mut requested = Vec::new();
+    let output = format("config.yaml.j2", "a:   1\n", |request| {
+        requested.push(request.file_path.to_path_buf());
+        Ok(Some(b"a: 1\n".to_vec()))
+    });
+    assert_eq!(output.as_deref(), Some("a: 1\n"));
+    assert_eq!(requested, [PathBuf::from("config.yaml")]);
+}
+
+#[test]
+fn template_file_with_placeholders() {
+    let mut requested = Vec::new();
+    let output = format(
+        "config.yaml.j2",
+        "a:   {{x}}\n{% if b %}\nc:   jinja\n{% endif %}\n",
+        |request| {
+            let text = String::from_utf8(request.file_bytes.to_vec()).unwrap();
+            requested.push(text.clone());
+            Ok(Some(text.replace(":   ", ": ").into_bytes()))
+        },
+    );
+    assert_eq!(
+        output.as_deref(),
+        Some("a: {{ x }}\n{% if b %}\nc: jinja\n{% endif %}\n")
+    );
+    assert_eq!(
+        requested,
+        ["a:   jinja_0_\njinja_1_\nc:   jinja\njinja_2_\n"]
+    );
+}
+
+#[test]
+fn template_file_with_broken_placeholders() {
+    let code = "a:   {{ x }}\n";
+    // host fails to format text with placeholders
+    let output = format("config.yaml.j2", code, |_| {
+        Err(anyhow::anyhow!("syntax error"))
+    });
+    assert_eq!(output, None);
+
+    // host drops placeholders
+    let output = format("config.yaml.j2", code, |_| Ok(Some(b"a: 1\n".to_vec())));
+    assert_eq!(output, None);
+
+    // host duplicates placeholders
+    let output = format("config.yaml.j2", code, |_| {
+        Ok(Some(b"a: jinja0_\nb: jinja0_\n".to_vec()))
+    });
+    assert_eq!(output, None);
+}
+
+#[test]
+fn resolve_config() {
+    let config = [
+        ("indentWidth", ConfigKeyValue::Number(4)),
+        ("operatorLinebreak", "before".to_string().into()),
+        ("exprDict.trailingComma", "never".to_string().into()),
+        ("args.preferSingleLine", true.into()),
+        ("params.preferSingleLine", ConfigKeyValue::Null),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value))
+    .collect();
+    let global_config = GlobalConfiguration {
+        line_width: Some(100),
+        indent_width: Some(8),
+        new_line_kind: Some(NewLineKind::CarriageReturnLineFeed),
+        ..Default::default()
+    };
+    let result = PrettyJinjaPluginHandler.resolve_config(config, &global_config);
+    assert_eq!(result.diagnostics.len(), 0);
+
+    let FormatOptions { layout, language } = result.config.options;
+    assert_eq!(layout.print_width, 100);
+    assert_eq!(layout.indent_width, 4);
+    assert!(matches!(layout.line_break, LineBreak::Crlf));
+    assert!(matches!(
+        language.operator_linebreak,
+        OperatorLineBreak::Before
+    ));
+    assert!(matches!(
+        language.expr_dict_trailing_comma,
+        Some(TrailingComma::Never)
+    ));
+    assert_eq!(language.args_prefer_single_line, Some(true));
+    assert_eq!(language.params_prefer_single_line, None);
+}
+
+#[test]
+fn resolve_invalid_config() {
+    let config = [
+        ("printWidth", ConfigKeyValue::Number(-1)),
+        ("trailingComma", "sometimes".to_string().into()),
+        ("exprList.trailingComma", "always".to_string().into()),
+        ("print_width", ConfigKeyValue::Number(100)),
+        ("unknown", true.into()),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value))
+    .collect();
+    let result = PrettyJinjaPluginHandler.resolve_config(config, &Default::default());
+    assert_eq!(
+        result
+            .diagnostics
+            .iter()
+            .map(|diagnostic| (&*diagnostic.property_name, &*diagnostic.message))
+            .collect::<Vec<_>>(),
+        [
+            ("printWidth", "invalid value for config `printWidth`"),
+            ("trailingComma", "invalid value for config `trailingComma`"),
+            ("print_width", "Unknown property in configuration"),
+            ("unknown", "Unknown property in configuration"),
+        ]
+    );
+
+    // other options are still resolved
+    let FormatOptions { layout, language } = result.config.options;
+    assert_eq!(layout.print_width, 80);
+    assert!(matches!(
+        language.expr_list_trailing_comma,
+        Some(TrailingComma::Always)
+    ));
+}
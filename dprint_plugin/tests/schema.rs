@@ -0,0 +1,138 @@
+use dprint_core::{
+    configuration::{ConfigKeyMap, ConfigKeyValue, GlobalConfiguration},
+    plugins::SyncPluginHandler,
+};
+use dprint_plugin_jinja::PrettyJinjaPluginHandler;
+use pretty_jinja::config::FormatOptions;
+use schemars::generate::SchemaSettings;
+use serde_json::{Map, Value, json};
+use similar_asserts::assert_eq;
+use std::fs;
+
+/// Generate JSON schema from config types,
+/// with keys of options converted to the keys used in `dprint.json`.
+fn generate_schema() -> Value {
+    let schema = SchemaSettings::draft07()
+        .into_generator()
+        .into_root_schema_for::<FormatOptions>();
+    let mut schema = schema.to_value();
+    join_description_lines(&mut schema);
+    let root = schema.as_object_mut().unwrap();
+
+    let properties = root["properties"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .map(|(key, value)| (to_config_key(key), value.clone()))
+        .collect::<Map<_, _>>();
+
+    let mut result = Map::new();
+    result.insert(
+        "$schema".into(),
+        "https://json-schema.org/draft-07/schema#".into(),
+    );
+    result.insert(
+        "$id".into(),
+        format!(
+            "https://plugins.dprint.dev/g-plane/pretty_jinja/v{}/schema.json",
+            env!("CARGO_PKG_VERSION")
+        )
+        .into(),
+    );
+    result.insert("title".into(), "Config".into());
+    result.insert(
+        "description".into(),
+        "Configuration for dprint-plugin-jinja.".into(),
+    );
+    result.insert("type".into(), "object".into());
+    if let Some(definitions) = root.remove("definitions") {
+        result.insert("definitions".into(), definitions);
+    }
+    result.insert("properties".into(), Value::Object(properties));
+    Value::Object(result)
+}
+
+/// Doc comments are wrapped as multiple lines, but descriptions in schema shouldn't be.
+fn join_description_lines(value: &mut Value) {
+    match value {
+        Value::Object(object) => object.iter_mut().for_each(|(key, value)| {
+            if let (true, Value::String(description)) = (key == "description", &mut *value) {
+                *description = description.replace('\n', " ");
+            } else {
+                join_description_lines(value);
+            }
+        }),
+        Value::Array(array) => array.iter_mut().for_each(join_description_lines),
+        _ => {}
+    }
+}
+
+/// Convert option name like `expr_dict.trailing_comma` to `exprDict.trailingComma`.
+fn to_config_key(name: &str) -> String {
+    name.split('.')
+        .map(|segment| {
+            let mut words = segment.split('_');
+            let first = words.next().unwrap_or_default().to_string();
+            words.fold(first, |mut key, word| {
+                let mut chars = word.chars();
+                if let Some(c) = chars.next() {
+                    key.extend(c.to_uppercase());
+                    key.push_str(chars.as_str());
+                }
+                key
+            })
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+#[test]
+fn schema_is_up_to_date() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/deployment/schema.json");
+    let schema = serde_json::to_string_pretty(&generate_schema()).unwrap() + "\n";
+    let bless = std::env::var_os("BLESS").is_some_and(|value| value != "0");
+    if bless {
+        fs::write(path, &schema).unwrap();
+    } else {
+        assert_eq!(
+            fs::read_to_string(path).unwrap(),
+            schema,
+            "schema.json is stale, run tests with `BLESS=1` to update it"
+        );
+    }
+}
+
+#[test]
+fn schema_keys_are_accepted() {
+    let schema = generate_schema();
+    let config = schema["properties"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .filter_map(|(key, property)| {
+            let value = match &property["default"] {
+                Value::Bool(value) => ConfigKeyValue::Bool(*value),
+                Value::Number(value) => ConfigKeyValue::Number(value.as_i64()? as i32),
+                Value::String(value) => ConfigKeyValue::String(value.clone()),
+                _ => return None,
+            };
+            Some((key.clone(), value))
+        })
+        .collect::<ConfigKeyMap>();
+    assert_eq!(
+        config.len(),
+        schema["properties"]
+            .as_object()
+            .unwrap()
+            .values()
+            .filter(|property| !property["default"].is_null())
+            .count()
+    );
+
+    let result = PrettyJinjaPluginHandler.resolve_config(config, &GlobalConfiguration::default());
+    assert_eq!(result.diagnostics.len(), 0, "{:?}", result.diagnostics);
+    assert_eq!(
+        schema["properties"]["exprDict.trailingComma"]["description"],
+        json!("Override `trailingComma` for dicts.")
+    );
+}
@@ -0,0 +1,59 @@
+use lsp_types::{Position, Range};
+use pretty_jinja::{TextRange, TextSize};
+
+/// Convert between byte offsets and LSP positions whose characters are counted in UTF-16.
+pub(crate) struct LineIndex<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub(crate) fn new(text: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { text, line_starts }
+    }
+
+    pub(crate) fn position(&self, offset: usize) -> Position {
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        let line_start = self.line_starts[line];
+        Position {
+            line: line as u32,
+            character: self.text[line_start..offset].encode_utf16().count() as u32,
+        }
+    }
+
+    /// Positions beyond the end of line or text are clamped.
+    pub(crate) fn offset(&self, position: Position) -> usize {
+        let Some(line_start) = self.line_starts.get(position.line as usize) else {
+            return self.text.len();
+        };
+        let mut character = 0;
+        self.text[*line_start..]
+            .char_indices()
+            .find(|(_, c)| {
+                if *c == '\n' || character >= position.character {
+                    true
+                } else {
+                    character += c.len_utf16() as u32;
+                    false
+                }
+            })
+            .map_or(self.text.len(), |(i, _)| line_start + i)
+    }
+
+    pub(crate) fn range(&self, range: TextRange) -> Range {
+        Range {
+            start: self.position(range.start().into()),
+            end: self.position(range.end().into()),
+        }
+    }
+
+    pub(crate) fn text_range(&self, range: Range) -> TextRange {
+        TextRange::new(
+            TextSize::new(self.offset(range.start) as u32),
+            TextSize::new(self.offset(range.end) as u32),
+        )
+    }
+}
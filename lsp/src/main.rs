@@ -0,0 +1,23 @@
+use crate::server::{Server, capabilities, server_info};
+use anyhow::Result;
+use lsp_server::Connection;
+use lsp_types::InitializeResult;
+
+mod line_index;
+mod server;
+
+fn main() -> Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+    let (id, _) = connection.initialize_start()?;
+    connection.initialize_finish(
+        id,
+        serde_json::to_value(InitializeResult {
+            capabilities: capabilities(),
+            server_info: Some(server_info()),
+        })?,
+    )?;
+    Server::new(&connection).run()?;
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
@@ -0,0 +1,266 @@
+use crate::line_index::LineIndex;
+use anyhow::Result;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, RequestId, Response};
+use lsp_types::{
+    Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentFormattingParams, DocumentOnTypeFormattingOptions,
+    DocumentOnTypeFormattingParams, DocumentRangeFormattingParams, FormattingOptions, OneOf,
+    PositionEncodingKind, PublishDiagnosticsParams, ServerCapabilities, ServerInfo,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Uri,
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+        PublishDiagnostics,
+    },
+    request::{Formatting, OnTypeFormatting, RangeFormatting, Request as _},
+};
+use pretty_jinja::{
+    TextRange, TextSize, check_template,
+    config::{Dialect, FormatOptions},
+    config_file::{ConfigFile, normalize_keys},
+    format_template_edits, format_template_range,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+pub(crate) fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        position_encoding: Some(PositionEncodingKind::UTF16),
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        document_range_formatting_provider: Some(OneOf::Left(true)),
+        // Both `}}` and `%}` end with `}`.
+        document_on_type_formatting_provider: Some(DocumentOnTypeFormattingOptions {
+            first_trigger_character: "}".into(),
+            more_trigger_character: None,
+        }),
+        ..Default::default()
+    }
+}
+
+pub(crate) fn server_info() -> ServerInfo {
+    ServerInfo {
+        name: "pretty_jinja_lsp".into(),
+        version: Some(env!("CARGO_PKG_VERSION").into()),
+    }
+}
+
+pub(crate) struct Server<'c> {
+    connection: &'c Connection,
+    documents: HashMap<Uri, String>,
+}
+
+impl<'c> Server<'c> {
+    pub(crate) fn new(connection: &'c Connection) -> Self {
+        Self {
+            connection,
+            documents: HashMap::new(),
+        }
+    }
+
+    /// Handle messages until client requests to shut down.
+    pub(crate) fn run(&mut self) -> Result<()> {
+        for message in &self.connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if self.connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    let response = self.handle_request(request);
+                    self.connection.sender.send(response.into())?;
+                }
+                Message::Notification(notification) => self.handle_notification(notification)?,
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&self, request: Request) -> Response {
+        let Request { id, method, params } = request;
+        let result = match method.as_str() {
+            Formatting::METHOD => parse_params(params).and_then(|params| self.format(params)),
+            RangeFormatting::METHOD => {
+                parse_params(params).and_then(|params| self.format_range(params))
+            }
+            OnTypeFormatting::METHOD => {
+                parse_params(params).and_then(|params| self.format_on_type(params))
+            }
+            _ => {
+                return Response::new_err(
+                    id,
+                    ErrorCode::MethodNotFound as i32,
+                    format!("unknown request `{method}`"),
+                );
+            }
+        };
+        to_response(id, result)
+    }
+
+    fn handle_notification(&mut self, notification: Notification) -> Result<()> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let uri = params.text_document.uri;
+                self.documents
+                    .insert(uri.clone(), params.text_document.text);
+                self.publish_diagnostics(uri)?;
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let uri = params.text_document.uri;
+                // Documents are always synced in full.
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents.insert(uri.clone(), change.text);
+                }
+                self.publish_diagnostics(uri)?;
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let uri = params.text_document.uri;
+                self.documents.remove(&uri);
+                self.send_diagnostics(uri, Vec::new())?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn publish_diagnostics(&self, uri: Uri) -> Result<()> {
+        let Some(text) = self.documents.get(&uri) else {
+            return Ok(());
+        };
+        let line_index = LineIndex::new(text);
+        // Syntax is still checked with default dialect if options can't be resolved,
+        // and the error is reported at the beginning of document.
+        let (dialect, config_error) = match resolve_options(&uri, &Default::default()) {
+            Ok(options) => (options.language.dialect, None),
+            Err(error) => (Dialect::default(), Some(error)),
+        };
+        let diagnostics = config_error
+            .into_iter()
+            .map(|message| Diagnostic {
+                severity: Some(DiagnosticSeverity::WARNING),
+                source: Some("pretty_jinja".into()),
+                message,
+                ..Default::default()
+            })
+            .chain(
+                check_template(text, dialect)
+                    .into_iter()
+                    .map(|(range, message)| Diagnostic {
+                        range: line_index.range(range),
+                        severity: Some(DiagnosticSeverity::ERROR),
+                        source: Some("pretty_jinja".into()),
+                        message,
+                        ..Default::default()
+                    }),
+            )
+            .collect();
+        self.send_diagnostics(uri, diagnostics)
+    }
+
+    fn send_diagnostics(&self, uri: Uri, diagnostics: Vec<Diagnostic>) -> Result<()> {
+        let params = PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        };
+        self.connection
+            .sender
+            .send(Notification::new(PublishDiagnostics::METHOD.into(), params).into())?;
+        Ok(())
+    }
+
+    fn format(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>, String> {
+        let uri = &params.text_document.uri;
+        let text = self.get_document(uri)?;
+        let options = resolve_options(uri, &params.options)?;
+        Ok(format_template_edits(text, &options)
+            .ok()
+            .map(|edits| to_text_edits(text, edits)))
+    }
+
+    fn format_range(
+        &self,
+        params: DocumentRangeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>, String> {
+        let uri = &params.text_document.uri;
+        let text = self.get_document(uri)?;
+        let options = resolve_options(uri, &params.options)?;
+        let range = LineIndex::new(text).text_range(params.range);
+        Ok(format_template_range(text, range, &options)
+            .ok()
+            .map(|edits| to_text_edits(text, edits)))
+    }
+
+    /// Format the tag which is just closed by typing `}}` or `%}`.
+    fn format_on_type(
+        &self,
+        params: DocumentOnTypeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>, String> {
+        let uri = &params.text_document_position.text_document.uri;
+        let text = self.get_document(uri)?;
+        let offset = LineIndex::new(text).offset(params.text_document_position.position);
+        let before = &text[..offset];
+        if !before.ends_with("}}") && !before.ends_with("%}") {
+            return Ok(None);
+        }
+        let options = resolve_options(uri, &params.options)?;
+        // Use offset before the last `}`, so the next adjacent tag won't be formatted.
+        let range = TextRange::empty(TextSize::new(offset as u32 - 1));
+        Ok(format_template_range(text, range, &options)
+            .ok()
+            .map(|edits| to_text_edits(text, edits)))
+    }
+
+    fn get_document(&self, uri: &Uri) -> Result<&str, String> {
+        self.documents
+            .get(uri)
+            .map(String::as_str)
+            .ok_or_else(|| format!("document `{}` isn't opened", uri.as_str()))
+    }
+}
+
+fn parse_params<P: DeserializeOwned>(params: Value) -> Result<P, String> {
+    serde_json::from_value(params).map_err(|error| format!("invalid params: {error}"))
+}
+
+fn to_response(id: RequestId, result: Result<Option<Vec<TextEdit>>, String>) -> Response {
+    match result {
+        Ok(edits) => Response::new_ok(id, edits),
+        Err(message) => Response::new_err(id, ErrorCode::RequestFailed as i32, message),
+    }
+}
+
+fn to_text_edits(text: &str, edits: Vec<(TextRange, String)>) -> Vec<TextEdit> {
+    let line_index = LineIndex::new(text);
+    edits
+        .into_iter()
+        .map(|(range, new_text)| TextEdit {
+            range: line_index.range(range),
+            new_text,
+        })
+        .collect()
+}
+
+/// Indentation from editor is used by default,
+/// and it can be overridden by the nearest configuration file.
+fn resolve_options(uri: &Uri, editor_options: &FormattingOptions) -> Result<FormatOptions, String> {
+    let mut options = Map::new();
+    options.insert("indent_width".into(), editor_options.tab_size.into());
+    options.insert("use_tabs".into(), (!editor_options.insert_spaces).into());
+    if let Some(path) = url::Url::parse(uri.as_str())
+        .ok()
+        .and_then(|url| url.to_file_path().ok())
+        && let Some(config_file) = ConfigFile::find(&path)?
+    {
+        // keys from editor are in snake case, while config file may use camel case
+        options.extend(normalize_keys(config_file.options_for(&path)));
+    }
+    serde_json::from_value(Value::Object(options))
+        .map_err(|error| format!("invalid format options: {error}"))
+}
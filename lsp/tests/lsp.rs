@@ -0,0 +1,302 @@
+use serde_json::{Value, json};
+use similar_asserts::assert_eq;
+use std::{
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    path::PathBuf,
+    process::{Child, ChildStdout, Command, Stdio},
+};
+
+/// Minimal client which talks to server over stdin and stdout.
+struct Client {
+    child: Child,
+    stdout: BufReader<ChildStdout>,
+    next_id: i64,
+}
+
+impl Client {
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_pretty_jinja_lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut client = Client {
+            child,
+            stdout,
+            next_id: 0,
+        };
+        let result = client.request("initialize", json!({ "capabilities": {} }));
+        assert_eq!(result["serverInfo"]["name"], "pretty_jinja_lsp");
+        client.notify("initialized", json!({}));
+        client
+    }
+
+    fn send(&mut self, message: Value) {
+        let body = message.to_string();
+        let stdin = self.child.stdin.as_mut().unwrap();
+        write!(stdin, "Content-Length: {}\r\n\r\n{body}", body.len()).unwrap();
+        stdin.flush().unwrap();
+    }
+
+    fn recv(&mut self) -> Value {
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            self.stdout.read_line(&mut line).unwrap();
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(value) = line.strip_prefix("Content-Length: ") {
+                length = value.parse().unwrap();
+            }
+        }
+        let mut body = vec![0; length];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        loop {
+            let message = self.recv();
+            if message["id"] == id {
+                return message["result"].clone();
+            }
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    fn recv_diagnostics(&mut self) -> Value {
+        loop {
+            let message = self.recv();
+            if message["method"] == "textDocument/publishDiagnostics" {
+                return message["params"]["diagnostics"].clone();
+            }
+        }
+    }
+
+    fn open(&mut self, uri: &str, text: &str) -> Value {
+        self.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": { "uri": uri, "languageId": "jinja", "version": 1, "text": text }
+            }),
+        );
+        self.recv_diagnostics()
+    }
+
+    fn shutdown(mut self) {
+        assert_eq!(self.request("shutdown", Value::Null), Value::Null);
+        self.notify("exit", Value::Null);
+        assert!(self.child.wait().unwrap().success());
+    }
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("pretty_jinja_lsp_{name}"));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn file_uri(path: PathBuf) -> String {
+    url::Url::from_file_path(path).unwrap().to_string()
+}
+
+fn editor_options() -> Value {
+    json!({ "tabSize": 4, "insertSpaces": true })
+}
+
+#[test]
+fn formatting() {
+    let dir = temp_dir("formatting");
+    let uri = file_uri(dir.join("a.j2"));
+    let mut client = Client::start();
+    client.open(&uri, "<p>{{a+1}}</p>\n{%if  b%}\n");
+
+    let edits = client.request(
+        "textDocument/formatting",
+        json!({ "textDocument": { "uri": uri }, "options": editor_options() }),
+    );
+    assert_eq!(
+        edits,
+        json!([
+            { "range": { "start": { "line": 0, "character": 5 }, "end": { "line": 0, "character": 5 } }, "newText": " " },
+            { "range": { "start": { "line": 0, "character": 6 }, "end": { "line": 0, "character": 6 } }, "newText": " " },
+            { "range": { "start": { "line": 0, "character": 7 }, "end": { "line": 0, "character": 7 } }, "newText": " " },
+            { "range": { "start": { "line": 0, "character": 8 }, "end": { "line": 0, "character": 8 } }, "newText": " " },
+            { "range": { "start": { "line": 1, "character": 2 }, "end": { "line": 1, "character": 2 } }, "newText": " " },
+            { "range": { "start": { "line": 1, "character": 4 }, "end": { "line": 1, "character": 6 } }, "newText": " " },
+            { "range": { "start": { "line": 1, "character": 7 }, "end": { "line": 1, "character": 7 } }, "newText": " " },
+        ])
+    );
+
+    let edits = client.request(
+        "textDocument/rangeFormatting",
+        json!({
+            "textDocument": { "uri": uri },
+            "range": { "start": { "line": 1, "character": 0 }, "end": { "line": 1, "character": 3 } },
+            "options": editor_options(),
+        }),
+    );
+    assert_eq!(edits.as_array().unwrap().len(), 3);
+    assert!(
+        edits
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|edit| edit["range"]["start"]["line"] == 1)
+    );
+
+    client.shutdown();
+}
+
+#[test]
+fn on_type_formatting() {
+    let dir = temp_dir("on_type");
+    let uri = file_uri(dir.join("a.j2"));
+    let mut client = Client::start();
+    client.open(&uri, "{{a+1}}{{b+1}}\n");
+
+    let request = |character| {
+        json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": 0, "character": character },
+            "ch": "}",
+            "options": editor_options(),
+        })
+    };
+    let edits = client.request("textDocument/onTypeFormatting", request(7));
+    assert_eq!(
+        edits
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|edit| edit["range"]["start"]["character"].as_u64().unwrap())
+            .collect::<Vec<_>>(),
+        [2, 3, 4, 5]
+    );
+
+    let edits = client.request("textDocument/onTypeFormatting", request(6));
+    assert_eq!(edits, Value::Null);
+
+    client.shutdown();
+}
+
+#[test]
+fn diagnostics() {
+    let dir = temp_dir("diagnostics");
+    let uri = file_uri(dir.join("a.j2"));
+    let mut client = Client::start();
+    let diagnostics = client.open(&uri, "ä\n  {{ a + }}\n");
+    assert_eq!(
+        diagnostics,
+        json!([{
+            "range": { "start": { "line": 1, "character": 7 }, "end": { "line": 1, "character": 8 } },
+            "severity": 1,
+            "source": "pretty_jinja",
+            "message": "unexpected `+`",
+        }])
+    );
+
+    client.notify(
+        "textDocument/didChange",
+        json!({
+            "textDocument": { "uri": uri, "version": 2 },
+            "contentChanges": [{ "text": "{{ a + 1 }}" }],
+        }),
+    );
+    assert_eq!(client.recv_diagnostics(), json!([]));
+
+    let edits = client.request(
+        "textDocument/formatting",
+        json!({ "textDocument": { "uri": uri }, "options": editor_options() }),
+    );
+    assert_eq!(edits, json!([]));
+
+    client.shutdown();
+}
+
+#[test]
+fn config_file() {
+    let dir = temp_dir("config_file");
+    fs::write(dir.join(".pretty_jinja.toml"), "bracket_spacing = true\n").unwrap();
+    let uri = file_uri(dir.join("a.j2"));
+    let mut client = Client::start();
+    client.open(&uri, "{{ [1] }}");
+
+    let edits = client.request(
+        "textDocument/formatting",
+        json!({ "textDocument": { "uri": uri }, "options": editor_options() }),
+    );
+    assert_eq!(
+        edits
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|edit| edit["newText"].as_str().unwrap())
+            .collect::<Vec<_>>(),
+        [" ", " "]
+    );
+
+    client.shutdown();
+}
+
+#[test]
+fn config_file_in_camel_case() {
+    let dir = temp_dir("config_file_in_camel_case");
+    fs::write(dir.join(".pretty_jinja.json"), r#"{ "indentWidth": 2 }"#).unwrap();
+    let uri = file_uri(dir.join("a.j2"));
+    let mut client = Client::start();
+    client.open(&uri, "{{ foo(aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa, bbbbbbbbbbbbbbbbbbbbbbbbbbbbbb) }}");
+
+    let edits = client.request(
+        "textDocument/formatting",
+        json!({ "textDocument": { "uri": uri }, "options": editor_options() }),
+    );
+    assert_eq!(
+        edits
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|edit| edit["newText"].as_str().unwrap())
+            .collect::<Vec<_>>(),
+        [
+            "\n  aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa,\n  bbbbbbbbbbbbbbbbbbbbbbbbbbbbbb,\n"
+        ]
+    );
+
+    client.shutdown();
+}
+
+#[test]
+fn invalid_config_file() {
+    let dir = temp_dir("invalid_config_file");
+    fs::write(
+        dir.join(".pretty_jinja.toml"),
+        "dialect = \"twig\"\nprint_width = \"wide\"\n",
+    )
+    .unwrap();
+    let uri = file_uri(dir.join("a.j2"));
+    let mut client = Client::start();
+    let diagnostics = client.open(&uri, "{{ a }}");
+    let diagnostics = diagnostics.as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["severity"], 2);
+    assert!(
+        diagnostics[0]["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("invalid format options")
+    );
+
+    client.shutdown();
+}
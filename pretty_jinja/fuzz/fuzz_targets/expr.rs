@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pretty_jinja::{format_expr, verify_expr};
+
+fuzz_target!(|code: &str| {
+    let options = Default::default();
+    // Formatted code of valid input must be reparsable, stable and have the same meaning.
+    if format_expr(code, &options).is_ok() {
+        verify_expr(code, &options).unwrap();
+    }
+});
@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pretty_jinja::{format_stmt, verify_stmt};
+
+fuzz_target!(|code: &str| {
+    let options = Default::default();
+    // Formatted code of valid input must be reparsable, stable and have the same meaning.
+    if format_stmt(code, &options).is_ok() {
+        verify_stmt(code, &options).unwrap();
+    }
+});
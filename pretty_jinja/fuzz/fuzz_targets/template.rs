@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pretty_jinja::{check_template, format_template, verify_template};
+
+fuzz_target!(|code: &str| {
+    let options = Default::default();
+    let _ = check_template(code, Default::default());
+    // Formatted code of valid input must be reparsable, stable and have the same meaning.
+    if format_template(code, &options).is_ok() {
+        verify_template(code, &options).unwrap();
+    }
+});
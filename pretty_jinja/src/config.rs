@@ -2,9 +2,11 @@
 
 #[cfg(feature = "config_serde")]
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Clone, Debug, Default)]
 #[cfg_attr(feature = "config_serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "config_schema", derive(schemars::JsonSchema))]
 #[cfg_attr(feature = "config_serde", serde(default))]
 pub struct FormatOptions {
     #[cfg_attr(feature = "config_serde", serde(flatten))]
@@ -15,18 +17,26 @@ pub struct FormatOptions {
 
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "config_serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "config_schema", derive(schemars::JsonSchema))]
 #[cfg_attr(feature = "config_serde", serde(default))]
 /// Configuration related to layout, such as indentation or print width.
 pub struct LayoutOptions {
+    /// The line width limitation that Pretty Jinja should *(but not must)* avoid exceeding.
+    /// Pretty Jinja will try its best to keep line width less than this value,
+    /// but it may exceed for some cases, for example, a very very long single word.
     #[cfg_attr(feature = "config_serde", serde(alias = "printWidth"))]
     pub print_width: usize,
 
+    /// Specify use space or tab for indentation.
     #[cfg_attr(feature = "config_serde", serde(alias = "useTabs"))]
     pub use_tabs: bool,
 
+    /// Size of indentation. When enabled `useTabs`, this option may be disregarded,
+    /// since only one tab will be inserted when indented once.
     #[cfg_attr(feature = "config_serde", serde(alias = "indentWidth"))]
     pub indent_width: usize,
 
+    /// Specify whether use `\n` (LF) or `\r\n` (CRLF) for line break.
     #[cfg_attr(
         feature = "config_serde",
         serde(alias = "lineBreak", alias = "linebreak")
@@ -47,10 +57,13 @@ impl Default for LayoutOptions {
 
 #[derive(Clone, Debug, Default)]
 #[cfg_attr(feature = "config_serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "config_schema", derive(schemars::JsonSchema))]
 #[cfg_attr(feature = "config_serde", serde(rename_all = "kebab-case"))]
 pub enum LineBreak {
+    /// Use `\n` (LF) for line break.
     #[default]
     Lf,
+    /// Use `\r\n` (CRLF) for line break.
     Crlf,
 }
 
@@ -72,31 +85,41 @@ impl From<LayoutOptions> for tiny_pretty::PrintOptions {
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug)]
 #[cfg_attr(feature = "config_serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "config_schema", derive(schemars::JsonSchema))]
 #[cfg_attr(feature = "config_serde", serde(default))]
 /// Configuration related to syntax.
 pub struct LanguageOptions {
+    /// Template language whose syntax should be accepted.
+    pub dialect: Dialect,
+
+    /// Control whether line break should come before or after operators.
     #[cfg_attr(feature = "config_serde", serde(alias = "operatorLineBreak"))]
     pub operator_linebreak: OperatorLineBreak,
 
+    /// Control whether trailing comma should be inserted or not.
     #[cfg_attr(feature = "config_serde", serde(alias = "trailingComma"))]
     pub trailing_comma: TrailingComma,
+    /// Override `trailingComma` for arguments of function calls.
     #[cfg_attr(
         feature = "config_serde",
         serde(rename = "args.trailing_comma", alias = "args.trailingComma")
     )]
     pub args_trailing_comma: Option<TrailingComma>,
+    /// Override `trailingComma` for dicts.
     #[cfg_attr(
         feature = "config_serde",
         serde(rename = "expr_dict.trailing_comma", alias = "exprDict.trailingComma")
     )]
     pub expr_dict_trailing_comma: Option<TrailingComma>,
+    /// Override `trailingComma` for lists.
     #[cfg_attr(
         feature = "config_serde",
         serde(rename = "expr_list.trailing_comma", alias = "exprList.trailingComma")
     )]
     pub expr_list_trailing_comma: Option<TrailingComma>,
+    /// Override `trailingComma` for tuples.
     #[cfg_attr(
         feature = "config_serde",
         serde(
@@ -105,19 +128,24 @@ pub struct LanguageOptions {
         )
     )]
     pub expr_tuple_trailing_comma: Option<TrailingComma>,
+    /// Override `trailingComma` for parameters of macros.
     #[cfg_attr(
         feature = "config_serde",
         serde(rename = "params.trailing_comma", alias = "params.trailingComma")
     )]
     pub params_trailing_comma: Option<TrailingComma>,
 
+    /// Control whether items should be placed on single line as possible,
+    /// even they're originally on multiple lines.
     #[cfg_attr(feature = "config_serde", serde(alias = "preferSingleLine"))]
     pub prefer_single_line: bool,
+    /// Override `preferSingleLine` for arguments of function calls.
     #[cfg_attr(
         feature = "config_serde",
         serde(rename = "args.prefer_single_line", alias = "args.preferSingleLine")
     )]
     pub args_prefer_single_line: Option<bool>,
+    /// Override `preferSingleLine` for dicts.
     #[cfg_attr(
         feature = "config_serde",
         serde(
@@ -126,6 +154,7 @@ pub struct LanguageOptions {
         )
     )]
     pub expr_dict_prefer_single_line: Option<bool>,
+    /// Override `preferSingleLine` for lists.
     #[cfg_attr(
         feature = "config_serde",
         serde(
@@ -134,6 +163,7 @@ pub struct LanguageOptions {
         )
     )]
     pub expr_list_prefer_single_line: Option<bool>,
+    /// Override `preferSingleLine` for tuples.
     #[cfg_attr(
         feature = "config_serde",
         serde(
@@ -142,6 +172,7 @@ pub struct LanguageOptions {
         )
     )]
     pub expr_tuple_prefer_single_line: Option<bool>,
+    /// Override `preferSingleLine` for parameters of macros.
     #[cfg_attr(
         feature = "config_serde",
         serde(
@@ -151,37 +182,182 @@ pub struct LanguageOptions {
     )]
     pub params_prefer_single_line: Option<bool>,
 
+    /// Control whether whitespace should be inserted between braces or not.
     #[cfg_attr(feature = "config_serde", serde(alias = "braceSpacing"))]
     pub brace_spacing: bool,
 
+    /// Control whether whitespace should be inserted between brackets or not.
     #[cfg_attr(feature = "config_serde", serde(alias = "bracketSpacing"))]
     pub bracket_spacing: bool,
 
+    /// Control whether whitespace should be inserted between parentheses of arguments or not.
     #[cfg_attr(feature = "config_serde", serde(alias = "argsParenSpacing"))]
     pub args_paren_spacing: bool,
 
+    /// Control whether whitespace should be inserted between parentheses of parameters or not.
     #[cfg_attr(feature = "config_serde", serde(alias = "paramsParenSpacing"))]
     pub params_paren_spacing: bool,
 
+    /// Control whether whitespace should be inserted between parentheses of tuple or not.
     #[cfg_attr(feature = "config_serde", serde(alias = "tupleParenSpacing"))]
     pub tuple_paren_spacing: bool,
+
+    /// Control whether whitespace should be inserted before colon of dict items or not.
+    #[cfg_attr(feature = "config_serde", serde(alias = "dictColonSpaceBefore"))]
+    pub dict_colon_space_before: bool,
+
+    /// Control whether whitespace should be inserted after colon of dict items or not.
+    #[cfg_attr(feature = "config_serde", serde(alias = "dictColonSpaceAfter"))]
+    pub dict_colon_space_after: bool,
+
+    /// Control the quotes of string keys in dict.
+    #[cfg_attr(feature = "config_serde", serde(alias = "dictKeyQuote"))]
+    pub dict_key_quote: Quotes,
+
+    /// Control whether values of dict items should be aligned vertically
+    /// when dict is placed on multiple lines.
+    #[cfg_attr(feature = "config_serde", serde(alias = "dictAlignValues"))]
+    pub dict_align_values: bool,
+
+    /// Control whether parentheses that don't change the meaning of expression
+    /// should be removed or not.
+    #[cfg_attr(feature = "config_serde", serde(alias = "removeRedundantParens"))]
+    pub remove_redundant_parens: bool,
+
+    /// Control whether parentheses should be added around `and` expression
+    /// inside `or` expression or not.
+    #[cfg_attr(feature = "config_serde", serde(alias = "clarifyMixedAndOr"))]
+    pub clarify_mixed_and_or: bool,
+
+    /// Control whether whitespace should be inserted around `=` of keyword arguments
+    /// and parameter defaults or not.
+    #[cfg_attr(feature = "config_serde", serde(alias = "kwargEqSpacing"))]
+    pub kwarg_eq_spacing: bool,
+
+    /// Control whether `=` of parameter defaults should be aligned vertically
+    /// when parameters are placed on multiple lines.
+    #[cfg_attr(feature = "config_serde", serde(alias = "paramsAlignDefaults"))]
+    pub params_align_defaults: bool,
+}
+
+impl Default for LanguageOptions {
+    fn default() -> Self {
+        Self {
+            dialect: Default::default(),
+            operator_linebreak: Default::default(),
+            trailing_comma: Default::default(),
+            args_trailing_comma: None,
+            expr_dict_trailing_comma: None,
+            expr_list_trailing_comma: None,
+            expr_tuple_trailing_comma: None,
+            params_trailing_comma: None,
+            prefer_single_line: false,
+            args_prefer_single_line: None,
+            expr_dict_prefer_single_line: None,
+            expr_list_prefer_single_line: None,
+            expr_tuple_prefer_single_line: None,
+            params_prefer_single_line: None,
+            brace_spacing: false,
+            bracket_spacing: false,
+            args_paren_spacing: false,
+            params_paren_spacing: false,
+            tuple_paren_spacing: false,
+            dict_colon_space_before: false,
+            dict_colon_space_after: true,
+            dict_key_quote: Default::default(),
+            dict_align_values: false,
+            remove_redundant_parens: false,
+            clarify_mixed_and_or: false,
+            kwarg_eq_spacing: false,
+            params_align_defaults: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "config_serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "config_schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "config_serde", serde(rename_all = "kebab-case"))]
+pub enum Dialect {
+    /// Jinja, which is also the base of other dialects.
+    #[default]
+    Jinja,
+    /// Nunjucks, which adds regex literals, `===` and `!==` operators,
+    /// and `{% asyncEach %}` and `{% asyncAll %}` loops.
+    Nunjucks,
+    /// Twig, which adds ranges, null-coalescing, bitwise, `<=>` and string matching operators,
+    /// conditional expressions like `a ? b : c`, arrow functions, `~` whitespace control,
+    /// and `{% verbatim %}` blocks.
+    Twig,
+    /// Tera, which adds macro namespaces like `macros::input()`, strings quoted with backticks,
+    /// and `{% set_global %}`.
+    Tera,
+    /// Django template language, which has filter arguments like `default:"x"`,
+    /// keyword arguments of tags like `{% with a=b %}`, and no function calls.
+    /// Tags are always printed on single line, since Django doesn't allow line breaks in tags.
+    Django,
+    /// dbt, where `config()` calls with multiple arguments are always broken
+    /// into one argument per line.
+    Dbt,
 }
 
 #[derive(Clone, Copy, Debug, Default)]
 #[cfg_attr(feature = "config_serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "config_schema", derive(schemars::JsonSchema))]
 #[cfg_attr(feature = "config_serde", serde(rename_all = "kebab-case"))]
 pub enum OperatorLineBreak {
+    /// Line break will come before operators.
     Before,
+    /// Line break will come after operators.
     #[default]
     After,
 }
 
 #[derive(Clone, Copy, Debug, Default)]
 #[cfg_attr(feature = "config_serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "config_schema", derive(schemars::JsonSchema))]
 #[cfg_attr(feature = "config_serde", serde(rename_all = "kebab-case"))]
 pub enum TrailingComma {
+    /// Do not insert trailing comma.
     Never,
+    /// Always insert trailing comma.
     Always,
+    /// Insert trailing comma when it lays as multiple lines,
+    /// and do not insert trailing comma if it's single line.
     #[default]
     OnlyMultiLine,
 }
+
+#[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "config_serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "config_schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "config_serde", serde(rename_all = "kebab-case"))]
+pub enum Quotes {
+    /// Keep quotes as-is.
+    #[default]
+    Preserve,
+    /// Use double quotes as possible.
+    Double,
+    /// Use single quotes as possible.
+    Single,
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "config_serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "config_serde", serde(default))]
+/// Configuration of linter.
+pub struct LintOptions {
+    /// Template language whose syntax should be accepted.
+    pub dialect: Dialect,
+    /// Severity of rules by rule name, which overrides default severity of rules.
+    pub rules: HashMap<String, Severity>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "config_serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "config_serde", serde(rename_all = "kebab-case"))]
+pub enum Severity {
+    Off,
+    Warning,
+    Error,
+}
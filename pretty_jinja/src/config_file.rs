@@ -0,0 +1,222 @@
+//! Discovering and loading configuration files.
+//!
+//! When looking for configuration of a template file, directories are walked up from the file,
+//! and the first directory containing one of these files wins:
+//!
+//! - `.pretty_jinja.toml`
+//! - `.prettyjinjarc` (JSON)
+//! - `.pretty_jinja.json`
+//! - `pyproject.toml` with `[tool.pretty_jinja]` table
+//!
+//! Options can be overridden for specific files:
+//!
+//! ```toml
+//! print_width = 100
+//!
+//! [[overrides]]
+//! files = ["*.sql.j2", "emails/**"]
+//! options = { print_width = 120 }
+//! ```
+
+use crate::config::FormatOptions;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+const CONFIG_FILE_NAMES: [&str; 4] = [
+    ".pretty_jinja.toml",
+    ".prettyjinjarc",
+    ".pretty_jinja.json",
+    "pyproject.toml",
+];
+
+#[derive(Clone, Debug, Default)]
+/// Loaded configuration file.
+pub struct ConfigFile {
+    /// Path of configuration file.
+    pub path: PathBuf,
+    options: Map<String, Value>,
+    overrides: Vec<Override>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+struct RawConfig {
+    #[serde(default)]
+    overrides: Vec<Override>,
+    #[serde(flatten)]
+    options: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Override {
+    files: Files,
+    #[serde(default)]
+    options: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+enum Files {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+#[derive(Deserialize)]
+struct PyProject {
+    tool: Option<PyProjectTool>,
+}
+#[derive(Deserialize)]
+struct PyProjectTool {
+    pretty_jinja: Option<RawConfig>,
+}
+
+impl ConfigFile {
+    /// Load configuration file.
+    /// File whose name ends with `.toml` is parsed as TOML, otherwise it's parsed as JSON.
+    ///
+    /// For `pyproject.toml`, `None` will be returned if there's no `[tool.pretty_jinja]` table.
+    pub fn load(path: &Path) -> Result<Option<Self>, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|error| format!("failed to read `{}`: {error}", path.display()))?;
+        let raw = if path
+            .file_name()
+            .is_some_and(|name| name == "pyproject.toml")
+        {
+            toml::from_str::<PyProject>(&content)
+                .map_err(|error| format!("invalid config file `{}`: {error}", path.display()))?
+                .tool
+                .and_then(|tool| tool.pretty_jinja)
+        } else if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str::<RawConfig>(&content)
+                .map(Some)
+                .map_err(|error| format!("invalid config file `{}`: {error}", path.display()))?
+        } else {
+            serde_json::from_str::<RawConfig>(&content)
+                .map(Some)
+                .map_err(|error| format!("invalid config file `{}`: {error}", path.display()))?
+        };
+        Ok(raw.map(|raw| ConfigFile {
+            path: path.to_path_buf(),
+            options: normalize_keys(raw.options),
+            overrides: raw
+                .overrides
+                .into_iter()
+                .map(|item| Override {
+                    files: item.files,
+                    options: normalize_keys(item.options),
+                })
+                .collect(),
+        }))
+    }
+
+    /// Find the nearest configuration file by walking up from the given file or directory.
+    pub fn find(path: &Path) -> Result<Option<Self>, String> {
+        let path = std::path::absolute(path)
+            .map_err(|error| format!("failed to resolve `{}`: {error}", path.display()))?;
+        let path = path.as_path();
+        let start = if path.is_dir() {
+            Some(path)
+        } else {
+            path.parent()
+        };
+        for dir in start.into_iter().flat_map(Path::ancestors) {
+            for name in CONFIG_FILE_NAMES {
+                let candidate = dir.join(name);
+                if candidate.is_file()
+                    && let Some(config) = Self::load(&candidate)?
+                {
+                    return Ok(Some(config));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Get raw options for the given file, with matched overrides applied.
+    ///
+    /// Keys in the returned map are the same as serialized [`FormatOptions`].
+    pub fn options_for(&self, file: &Path) -> Map<String, Value> {
+        let base = std::path::absolute(&self.path).unwrap_or_else(|_| self.path.clone());
+        let base = base.parent().unwrap_or(Path::new(""));
+        let file = std::path::absolute(file).unwrap_or_else(|_| file.to_path_buf());
+        let relative = file.strip_prefix(base).unwrap_or(&file);
+        let mut options = self.options.clone();
+        self.overrides
+            .iter()
+            .filter(|item| {
+                let patterns = match &item.files {
+                    Files::Single(pattern) => std::slice::from_ref(pattern),
+                    Files::Multiple(patterns) => patterns,
+                };
+                patterns
+                    .iter()
+                    .any(|pattern| matches_glob(pattern, relative))
+            })
+            .for_each(|item| {
+                options.extend(item.options.clone());
+            });
+        options
+    }
+
+    /// Resolve format options for the given file, with matched overrides applied.
+    pub fn format_options_for(&self, file: &Path) -> Result<FormatOptions, String> {
+        serde_json::from_value(Value::Object(self.options_for(file)))
+            .map_err(|error| format!("invalid config file `{}`: {error}", self.path.display()))
+    }
+}
+
+/// Rename keys of raw options to keys of serialized [`FormatOptions`],
+/// so options from different sources can be merged
+/// even if one is written in camel case like `printWidth` and another in snake case.
+///
+/// Keys are compared with case and underscores ignored. Unknown keys are kept as-is.
+pub fn normalize_keys(options: Map<String, Value>) -> Map<String, Value> {
+    let Ok(Value::Object(known)) = serde_json::to_value(FormatOptions::default()) else {
+        return options;
+    };
+    options
+        .into_iter()
+        .map(|(key, value)| {
+            let simplified = simplify_key(&key);
+            let key = known
+                .keys()
+                .find(|known| simplify_key(known) == simplified)
+                .cloned()
+                .unwrap_or(key);
+            (key, value)
+        })
+        .collect()
+}
+fn simplify_key(key: &str) -> String {
+    key.chars()
+        .filter(|c| *c != '_')
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Pattern without slash matches file name only, like `.gitignore` does.
+fn matches_glob(pattern: &str, path: &Path) -> bool {
+    let Ok(glob) = glob::Pattern::new(pattern.trim_start_matches("./")) else {
+        return false;
+    };
+    if pattern.contains('/') {
+        glob.matches_path(path)
+    } else {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| glob.matches(name))
+    }
+}
+
+/// Resolve format options for the given template file
+/// from the nearest configuration file.
+/// Default options will be used if there's no configuration file.
+pub fn resolve_options(file: &Path) -> Result<FormatOptions, String> {
+    match ConfigFile::find(file)? {
+        Some(config) => config.format_options_for(file),
+        None => Ok(FormatOptions::default()),
+    }
+}
@@ -0,0 +1,107 @@
+use crate::syntax::{SyntaxKind, SyntaxNode};
+use rowan::{TextRange, TextSize};
+use std::ops::Range;
+
+/// Collect byte ranges of non-whitespace tokens, shifted by the given offset.
+/// BOM is treated as whitespace since formatter doesn't keep it.
+pub(crate) fn collect_tokens(node: &SyntaxNode, offset: usize, tokens: &mut Vec<Range<usize>>) {
+    tokens.extend(
+        node.descendants_with_tokens()
+            .filter_map(|node_or_token| node_or_token.into_token())
+            .filter(|token| !matches!(token.kind(), SyntaxKind::WHITESPACE | SyntaxKind::BOM))
+            .map(|token| {
+                let range = token.text_range();
+                usize::from(range.start()) + offset..usize::from(range.end()) + offset
+            }),
+    );
+}
+
+/// Compute text edits that turn old text into new text.
+///
+/// If both texts have the same tokens, only whitespaces between tokens will be edited.
+/// Otherwise, a single edit that replaces the different part will be returned.
+pub(crate) fn diff(
+    old: &str,
+    old_tokens: &[Range<usize>],
+    new: &str,
+    new_tokens: &[Range<usize>],
+) -> Vec<(TextRange, String)> {
+    if old == new {
+        return Vec::new();
+    }
+    let same_tokens = old_tokens.len() == new_tokens.len()
+        && old_tokens
+            .iter()
+            .zip(new_tokens)
+            .all(|(old_token, new_token)| old[old_token.clone()] == new[new_token.clone()]);
+    if !same_tokens {
+        return diff_text(old, new).into_iter().collect();
+    }
+
+    let gap_starts = |tokens: &[Range<usize>]| {
+        std::iter::once(0)
+            .chain(tokens.iter().map(|token| token.end))
+            .collect::<Vec<_>>()
+    };
+    let gap_ends = |tokens: &[Range<usize>], len: usize| {
+        tokens
+            .iter()
+            .map(|token| token.start)
+            .chain(std::iter::once(len))
+            .collect::<Vec<_>>()
+    };
+    gap_starts(old_tokens)
+        .into_iter()
+        .zip(gap_ends(old_tokens, old.len()))
+        .zip(
+            gap_starts(new_tokens)
+                .into_iter()
+                .zip(gap_ends(new_tokens, new.len())),
+        )
+        .filter(|((old_start, old_end), (new_start, new_end))| {
+            old[*old_start..*old_end] != new[*new_start..*new_end]
+        })
+        .map(|((old_start, old_end), (new_start, new_end))| {
+            (
+                to_text_range(old_start..old_end),
+                new[new_start..new_end].to_string(),
+            )
+        })
+        .collect()
+}
+
+/// Replace the part between common prefix and common suffix.
+fn diff_text(old: &str, new: &str) -> Option<(TextRange, String)> {
+    let prefix = old
+        .char_indices()
+        .zip(new.chars())
+        .find(|((_, a), b)| a != b)
+        .map(|((i, _), _)| i)
+        .unwrap_or_else(|| old.len().min(new.len()));
+    let suffix = old[prefix..]
+        .chars()
+        .rev()
+        .zip(new[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum::<usize>();
+    let old_range = prefix..old.len() - suffix;
+    let new_range = prefix..new.len() - suffix;
+    if old_range.is_empty() && new_range.is_empty() {
+        None
+    } else {
+        Some((to_text_range(old_range), new[new_range].to_string()))
+    }
+}
+
+pub(crate) fn to_text_range(range: Range<usize>) -> TextRange {
+    TextRange::new(
+        TextSize::new(range.start as u32),
+        TextSize::new(range.end as u32),
+    )
+}
+
+pub(crate) fn shift(edits: &mut [(TextRange, String)], offset: usize) {
+    let offset = TextSize::new(offset as u32);
+    edits.iter_mut().for_each(|(range, _)| *range += offset);
+}
@@ -1,30 +1,248 @@
-use crate::config::FormatOptions;
+use crate::config::{Dialect, FormatOptions, LintOptions};
+pub use rowan::{TextRange, TextSize};
 
 pub mod config;
+#[cfg(feature = "config_file")]
+pub mod config_file;
+mod edit;
+pub mod lint;
 mod parser;
 mod printer;
-mod syntax;
+pub mod syntax;
+mod template;
+mod verify;
+
+/// Parse Jinja expression into concrete syntax tree.
+///
+/// The syntax tree is lossless: text of the root node is always equal to the input,
+/// including BOM and whitespaces, so it can be used to build refactoring tools.
+pub fn parse_expr(code: &str, dialect: Dialect) -> Result<syntax::SyntaxNode, String> {
+    crate::parser::parse_expr(code, dialect).map_err(|err| err.to_string())
+}
+
+/// Parse Jinja statement into concrete syntax tree.
+///
+/// The syntax tree is lossless as [`parse_expr`].
+pub fn parse_stmt(code: &str, dialect: Dialect) -> Result<syntax::SyntaxNode, String> {
+    crate::parser::parse_stmt(code, dialect).map_err(|err| err.to_string())
+}
 
 /// Format Jinja expression which is generally from Jinja interpolation.
 pub fn format_expr(code: &str, options: &FormatOptions) -> Result<String, String> {
-    let node = match crate::parser::parse_expr(code) {
+    let node = match crate::parser::parse_expr(code, options.language.dialect) {
         Ok(node) => node,
         Err(err) => return Err(err.to_string()),
     };
-    Ok(tiny_pretty::print(
-        &printer::format(&node, options),
-        &options.layout.clone().into(),
-    ))
+    Ok(print(&node, options))
 }
 
 /// Format Jinja statement which is generally from Jinja block.
 pub fn format_stmt(code: &str, options: &FormatOptions) -> Result<String, String> {
-    let node = match crate::parser::parse_stmt(code) {
+    let node = match crate::parser::parse_stmt(code, options.language.dialect) {
         Ok(node) => node,
         Err(err) => return Err(err.to_string()),
     };
-    Ok(tiny_pretty::print(
-        &printer::format(&node, options),
-        &options.layout.clone().into(),
+    Ok(print(&node, options))
+}
+
+/// Format Jinja expression and return text edits against the original code,
+/// instead of the whole formatted code.
+pub fn format_expr_edits(
+    code: &str,
+    options: &FormatOptions,
+) -> Result<Vec<(TextRange, String)>, String> {
+    format_edits(code, options, parse_expr)
+}
+
+/// Format Jinja statement and return text edits against the original code,
+/// instead of the whole formatted code.
+pub fn format_stmt_edits(
+    code: &str,
+    options: &FormatOptions,
+) -> Result<Vec<(TextRange, String)>, String> {
+    format_edits(code, options, parse_stmt)
+}
+
+fn format_edits(
+    code: &str,
+    options: &FormatOptions,
+    parse: impl Fn(&str, Dialect) -> Result<syntax::SyntaxNode, String>,
+) -> Result<Vec<(TextRange, String)>, String> {
+    let dialect = options.language.dialect;
+    let node = parse(code, dialect)?;
+    let formatted = print(&node, options);
+    let mut old_tokens = Vec::new();
+    edit::collect_tokens(&node, 0, &mut old_tokens);
+    let mut new_tokens = Vec::new();
+    if let Ok(node) = parse(&formatted, dialect) {
+        edit::collect_tokens(&node, 0, &mut new_tokens);
+    }
+    Ok(edit::diff(code, &old_tokens, &formatted, &new_tokens))
+}
+
+/// Format Jinja expression, then verify that formatted code is stable
+/// and has the same meaning as the original code.
+///
+/// It's slower than [`format_expr`] and mainly for testing formatter.
+pub fn verify_expr(code: &str, options: &FormatOptions) -> Result<String, String> {
+    verify(code, options, parse_expr)
+}
+
+/// Format Jinja statement, then verify that formatted code is stable
+/// and has the same meaning as the original code.
+///
+/// It's slower than [`format_stmt`] and mainly for testing formatter.
+pub fn verify_stmt(code: &str, options: &FormatOptions) -> Result<String, String> {
+    verify(code, options, parse_stmt)
+}
+
+fn verify(
+    code: &str,
+    options: &FormatOptions,
+    parse: impl Fn(&str, Dialect) -> Result<syntax::SyntaxNode, String>,
+) -> Result<String, String> {
+    let dialect = options.language.dialect;
+    let node = parse(code, dialect)?;
+    let formatted = print(&node, options);
+    let formatted_node = parse(&formatted, dialect)
+        .map_err(|err| format!("formatted code can't be parsed: {err}"))?;
+    verify::compare(&node, &formatted_node, 0, dialect)?;
+    verify::check_stable(&formatted, &print(&formatted_node, options))?;
+    Ok(formatted)
+}
+
+fn print(node: &syntax::SyntaxNode, options: &FormatOptions) -> String {
+    let mut print_options = tiny_pretty::PrintOptions::from(options.layout.clone());
+    // Django doesn't allow line breaks in tags
+    if options.language.dialect == Dialect::Django {
+        print_options.width = usize::MAX;
+    }
+    tiny_pretty::print(&printer::format(node, options), &print_options)
+}
+
+/// Format Jinja template, including expressions, statements and comments in it.
+///
+/// Text outside Jinja tags and content of `{% raw %}` blocks are kept as-is.
+/// Tags with syntax errors, such as tags not supported by the dialect, are also kept as-is,
+/// which can be reported by [`check_template`].
+pub fn format_template(code: &str, options: &FormatOptions) -> Result<String, String> {
+    let tags = template::scan_tags(code, options.language.dialect)?;
+    Ok(template::format_tags(
+        code,
+        &tags,
+        options,
+        |_| true,
+        |_, _| {},
     ))
 }
+
+/// Find byte ranges of Jinja tags in template, including delimiters.
+///
+/// Content of `{% raw %}` blocks, or `{% verbatim %}` blocks of Twig and Django, isn't treated as tags.
+pub fn scan_template_tags(code: &str, dialect: Dialect) -> Result<Vec<TextRange>, String> {
+    template::scan_tags(code, dialect)
+        .map(|tags| {
+            tags.into_iter()
+                .map(|tag| edit::to_text_range(tag.range))
+                .collect()
+        })
+        .map_err(String::from)
+}
+
+/// Check syntax of Jinja template without formatting it,
+/// and return byte ranges and messages of syntax errors.
+///
+/// Each tag is checked separately, so errors of all tags will be reported.
+pub fn check_template(code: &str, dialect: Dialect) -> Vec<(TextRange, String)> {
+    match template::scan_tags(code, dialect) {
+        Ok(tags) => tags
+            .iter()
+            .filter_map(|tag| template::check_tag(code, tag, dialect))
+            .map(|(range, message)| (edit::to_text_range(range), message))
+            .collect(),
+        Err(error) => vec![(
+            edit::to_text_range(error.offset..code.len()),
+            error.message.to_string(),
+        )],
+    }
+}
+
+/// Format Jinja template, then verify that formatted template is stable
+/// and has the same meaning as the original template.
+///
+/// It's slower than [`format_template`] and mainly for testing formatter.
+pub fn verify_template(code: &str, options: &FormatOptions) -> Result<String, String> {
+    let formatted = format_template(code, options)?;
+    template::verify(code, &formatted, options.language.dialect)?;
+    verify::check_stable(&formatted, &format_template(&formatted, options)?)?;
+    Ok(formatted)
+}
+
+/// Format Jinja tags that intersect the given byte range of template,
+/// and return text edits of changed tags.
+///
+/// This is useful for editors to format selected text only.
+pub fn format_template_range(
+    code: &str,
+    range: TextRange,
+    options: &FormatOptions,
+) -> Result<Vec<(TextRange, String)>, String> {
+    let tags = template::scan_tags(code, options.language.dialect)?;
+    let mut edits = Vec::new();
+    template::format_tags(
+        code,
+        &tags,
+        options,
+        |tag| {
+            let tag_range = edit::to_text_range(tag.range.clone());
+            if range.is_empty() {
+                tag_range.contains_inclusive(range.start())
+            } else {
+                tag_range
+                    .intersect(range)
+                    .is_some_and(|range| !range.is_empty())
+            }
+        },
+        |tag, formatted| {
+            edits.append(&mut template::tag_edits(
+                code,
+                tag,
+                formatted,
+                options.language.dialect,
+            ))
+        },
+    );
+    Ok(edits)
+}
+
+/// Format Jinja template and return text edits against the original code,
+/// instead of the whole formatted code.
+pub fn format_template_edits(
+    code: &str,
+    options: &FormatOptions,
+) -> Result<Vec<(TextRange, String)>, String> {
+    let tags = template::scan_tags(code, options.language.dialect)?;
+    let mut edits = Vec::new();
+    template::format_tags(
+        code,
+        &tags,
+        options,
+        |_| true,
+        |tag, formatted| {
+            edits.append(&mut template::tag_edits(
+                code,
+                tag,
+                formatted,
+                options.language.dialect,
+            ))
+        },
+    );
+    Ok(edits)
+}
+
+/// Lint Jinja template with built-in rules, and return diagnostics sorted by position.
+///
+/// Tags with syntax errors are skipped, which can be reported by [`check_template`].
+pub fn lint_template(code: &str, options: &LintOptions) -> Result<Vec<lint::Diagnostic>, String> {
+    lint::lint(code, &lint::default_rules(), options)
+}
@@ -0,0 +1,386 @@
+//! Linting Jinja templates.
+//!
+//! Each tag of template is parsed separately, then every [`Rule`] checks syntax trees of all tags,
+//! so rules can look across tags, for example, the body of `{% macro %}` block.
+//! Tags with syntax errors are skipped, which are reported by [`check_template`](crate::check_template).
+
+use crate::{
+    config::{LintOptions, Severity},
+    parser,
+    syntax::{SyntaxKind, SyntaxNode, SyntaxToken},
+    template::{self, TagKind},
+};
+use rowan::{TextRange, TextSize, ast::support};
+use std::collections::HashSet;
+
+/// Jinja tag parsed for linting.
+#[derive(Clone, Debug)]
+pub struct ParsedTag {
+    /// Root node of tag content, which is `ROOT_EXPR` or `ROOT_STMT`.
+    pub node: SyntaxNode,
+    /// Byte offset of tag content in template.
+    pub offset: TextSize,
+}
+
+impl ParsedTag {
+    /// Convert range in syntax tree of this tag to range in template.
+    pub fn range(&self, range: TextRange) -> TextRange {
+        range + self.offset
+    }
+
+    /// Keyword of statement tag, such as `for` or `endfor`.
+    pub fn keyword(&self) -> Option<SyntaxToken> {
+        self.node
+            .first_child()
+            .filter(|_| self.node.kind() == SyntaxKind::ROOT_STMT)
+            .and_then(|stmt| support::token(&stmt, SyntaxKind::KEYWORD))
+    }
+
+    fn is_stmt(&self, keyword: &str) -> bool {
+        self.keyword().is_some_and(|token| token.text() == keyword)
+    }
+}
+
+/// Lint rule that checks syntax trees of template tags.
+pub trait Rule {
+    /// Name of rule, which is used as key of [`LintOptions::rules`].
+    fn name(&self) -> &'static str;
+
+    /// Severity of rule if it isn't configured.
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    /// Check all tags of template in order.
+    fn check(&self, tags: &[ParsedTag]) -> Vec<Problem>;
+}
+
+/// Problem found by a rule.
+#[derive(Clone, Debug)]
+pub struct Problem {
+    /// Byte range in template.
+    pub range: TextRange,
+    pub message: String,
+    /// Text edits against template that fix the problem.
+    /// It's empty if the problem can't be fixed automatically.
+    pub fix: Vec<(TextRange, String)>,
+}
+
+/// Problem reported by linter.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub rule: &'static str,
+    pub severity: Severity,
+    /// Byte range in template.
+    pub range: TextRange,
+    pub message: String,
+    /// Text edits against template that fix the problem.
+    /// It's empty if the problem can't be fixed automatically.
+    pub fix: Vec<(TextRange, String)>,
+}
+
+/// Built-in rules.
+pub fn default_rules() -> Vec<Box<dyn Rule>> {
+    vec![
+        Box::new(LoopVarShadowing),
+        Box::new(UnusedMacroParam),
+        Box::new(CompareNone),
+    ]
+}
+
+/// Lint Jinja template with the given rules, and return diagnostics sorted by position.
+pub fn lint(
+    code: &str,
+    rules: &[Box<dyn Rule>],
+    options: &LintOptions,
+) -> Result<Vec<Diagnostic>, String> {
+    let tags = template::scan_tags(code, options.dialect)?
+        .into_iter()
+        .filter_map(|tag| {
+            let content = &code[tag.inner.clone()];
+            let node = match tag.kind {
+                TagKind::Expr => parser::parse_expr(content, options.dialect).ok()?,
+                TagKind::Stmt => parser::parse_stmt(content, options.dialect).ok()?,
+                TagKind::Comment => return None,
+            };
+            Some(ParsedTag {
+                node,
+                offset: TextSize::new(tag.inner.start as u32),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut diagnostics = rules
+        .iter()
+        .flat_map(|rule| {
+            let severity = options
+                .rules
+                .get(rule.name())
+                .copied()
+                .unwrap_or_else(|| rule.default_severity());
+            if severity == Severity::Off {
+                return Vec::new();
+            }
+            rule.check(&tags)
+                .into_iter()
+                .map(|problem| Diagnostic {
+                    rule: rule.name(),
+                    severity,
+                    range: problem.range,
+                    message: problem.message,
+                    fix: problem.fix,
+                })
+                .collect()
+        })
+        .collect::<Vec<_>>();
+    diagnostics.sort_by_key(|diagnostic| diagnostic.range.start());
+    Ok(diagnostics)
+}
+
+/// Disallow loop variables that shadow variables of outer loops or the special `loop` variable.
+///
+/// `{% asyncEach %}` and `{% asyncAll %}` loops of Nunjucks are checked as well.
+pub struct LoopVarShadowing;
+
+impl Rule for LoopVarShadowing {
+    fn name(&self) -> &'static str {
+        "loop-var-shadowing"
+    }
+
+    fn check(&self, tags: &[ParsedTag]) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        // loop variables of enclosing `for` blocks
+        let mut scopes: Vec<Vec<SyntaxToken>> = Vec::new();
+        for tag in tags {
+            if tag.is_stmt("for") || tag.is_stmt("asyncEach") || tag.is_stmt("asyncAll") {
+                let bindings = tag
+                    .node
+                    .first_child()
+                    .filter(|stmt| stmt.kind() == SyntaxKind::STMT_FOR)
+                    .map(|stmt| {
+                        stmt.children_with_tokens()
+                            .filter_map(|element| element.into_token())
+                            .filter(|token| token.kind() == SyntaxKind::IDENT)
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+                problems.extend(bindings.iter().filter_map(|binding| {
+                    let name = binding.text();
+                    let message = if name == "loop" {
+                        "loop variable `loop` shadows the special `loop` variable".to_string()
+                    } else if scopes.iter().flatten().any(|outer| outer.text() == name) {
+                        format!("loop variable `{name}` shadows variable of outer loop")
+                    } else {
+                        return None;
+                    };
+                    Some(Problem {
+                        range: tag.range(binding.text_range()),
+                        message,
+                        fix: Vec::new(),
+                    })
+                }));
+                scopes.push(bindings);
+            } else if tag.is_stmt("endfor") || tag.is_stmt("endeach") || tag.is_stmt("endall") {
+                scopes.pop();
+            }
+        }
+        problems
+    }
+}
+
+/// Disallow macro parameters that are never used in macro body.
+pub struct UnusedMacroParam;
+
+impl Rule for UnusedMacroParam {
+    fn name(&self) -> &'static str {
+        "unused-macro-param"
+    }
+
+    fn check(&self, tags: &[ParsedTag]) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        // enclosing macros
+        let mut macros: Vec<MacroScope> = Vec::new();
+        for tag in tags {
+            if tag.is_stmt("endmacro") {
+                if let Some(MacroScope { params, used }) = macros.pop() {
+                    problems.extend(
+                        params
+                            .into_iter()
+                            .filter(|(_, name)| !used.contains(name))
+                            .map(|(range, name)| Problem {
+                                range,
+                                message: format!("macro parameter `{name}` is never used"),
+                                fix: Vec::new(),
+                            }),
+                    );
+                }
+                continue;
+            }
+
+            // default values of parameters are evaluated in outer scope
+            let names = used_variables(&tag.node).collect::<Vec<_>>();
+            macros
+                .iter_mut()
+                .for_each(|scope| scope.used.extend(names.iter().cloned()));
+
+            if tag.is_stmt("macro") {
+                let params = tag
+                    .node
+                    .first_child()
+                    .filter(|stmt| stmt.kind() == SyntaxKind::STMT_MACRO)
+                    .map(|stmt| {
+                        stmt.children()
+                            .filter(|param| param.kind() == SyntaxKind::PARAM)
+                            // `*args` and `**kwargs` are allowed to be unused
+                            .filter(|param| support::token(param, SyntaxKind::OPERATOR).is_none())
+                            .filter_map(|param| support::token(&param, SyntaxKind::IDENT))
+                            .map(|ident| (tag.range(ident.text_range()), ident.text().to_string()))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                macros.push(MacroScope {
+                    params,
+                    used: HashSet::new(),
+                });
+            }
+        }
+        problems
+    }
+}
+
+struct MacroScope {
+    /// Ranges in template and names of parameters.
+    params: Vec<(TextRange, String)>,
+    /// Variables used in macro body.
+    used: HashSet<String>,
+}
+
+/// Names of variables referenced in the given node,
+/// excluding attribute names, filter names and test names.
+fn used_variables(node: &SyntaxNode) -> impl Iterator<Item = String> {
+    node.descendants()
+        .filter(|node| node.kind() == SyntaxKind::EXPR_IDENT)
+        .filter(is_variable)
+        .filter_map(|node| support::token(&node, SyntaxKind::IDENT))
+        .map(|ident| ident.text().to_string())
+}
+fn is_variable(node: &SyntaxNode) -> bool {
+    let Some(parent) = node.parent() else {
+        return true;
+    };
+    let is_first = node.prev_sibling().is_none();
+    match parent.kind() {
+        SyntaxKind::EXPR_GET_ATTR
+        | SyntaxKind::EXPR_FILTER
+        | SyntaxKind::EXPR_TEST
+        | SyntaxKind::EXPR_PATH => is_first,
+        // filter name without arguments
+        SyntaxKind::EXPR_IDENT => false,
+        // filter name or test name with arguments
+        SyntaxKind::EXPR_CALL if is_first => {
+            parent.parent().is_none_or(|grand| match grand.kind() {
+                SyntaxKind::EXPR_FILTER | SyntaxKind::EXPR_TEST => parent.prev_sibling().is_none(),
+                SyntaxKind::STMT_FILTER => false,
+                _ => true,
+            })
+        }
+        _ => true,
+    }
+}
+
+/// Disallow comparing with `none` using `==` or `!=`, which should be `is none` test instead.
+pub struct CompareNone;
+
+impl Rule for CompareNone {
+    fn name(&self) -> &'static str {
+        "compare-none"
+    }
+
+    fn check(&self, tags: &[ParsedTag]) -> Vec<Problem> {
+        tags.iter()
+            .flat_map(|tag| {
+                tag.node
+                    .descendants()
+                    .filter(|node| node.kind() == SyntaxKind::EXPR_BIN)
+                    .filter_map(|node| {
+                        let operator = support::token(&node, SyntaxKind::OPERATOR)?;
+                        let negated = match operator.text() {
+                            "==" => false,
+                            "!=" => true,
+                            _ => return None,
+                        };
+                        let left = node.first_child()?;
+                        let right = node.last_child()?;
+                        let other = if is_none(&right) {
+                            left
+                        } else if is_none(&left) {
+                            right
+                        } else {
+                            return None;
+                        };
+                        Some(Problem {
+                            range: tag.range(node.text_range()),
+                            message: if negated {
+                                "use `is not none` instead of comparing with `none`".into()
+                            } else {
+                                "use `is none` instead of comparing with `none`".into()
+                            },
+                            fix: compare_none_fix(&node, &other, negated)
+                                .map(|text| vec![(tag.range(node.text_range()), text)])
+                                .unwrap_or_default(),
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+fn is_none(node: &SyntaxNode) -> bool {
+    node.kind() == SyntaxKind::EXPR_IDENT
+        && support::token(node, SyntaxKind::IDENT)
+            .is_some_and(|ident| matches!(ident.text(), "none" | "None"))
+}
+fn is_comparison(node: &SyntaxNode) -> bool {
+    node.kind() == SyntaxKind::EXPR_BIN
+        && support::token(node, SyntaxKind::OPERATOR).is_some_and(|operator| {
+            // operator of multiple words keeps whitespaces of original code, like `not  in`
+            matches!(
+                operator
+                    .text()
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .as_str(),
+                "==" | "!=" | "<" | ">" | "<=" | ">=" | "in" | "not in"
+            )
+        })
+}
+fn compare_none_fix(node: &SyntaxNode, other: &SyntaxNode, negated: bool) -> Option<String> {
+    // chained comparison like `a == b == none` can't be rewritten as a test
+    if is_comparison(other) || node.parent().is_some_and(|parent| is_comparison(&parent)) {
+        return None;
+    }
+    let other = if matches!(
+        other.kind(),
+        SyntaxKind::EXPR_IDENT
+            | SyntaxKind::EXPR_LITERAL
+            | SyntaxKind::EXPR_GET_ATTR
+            | SyntaxKind::EXPR_GET_ITEM
+            | SyntaxKind::EXPR_CALL
+            | SyntaxKind::EXPR_FILTER
+            | SyntaxKind::EXPR_PAREN
+            | SyntaxKind::EXPR_LIST
+            | SyntaxKind::EXPR_DICT
+            | SyntaxKind::EXPR_TUPLE
+    ) {
+        other.text().to_string()
+    } else {
+        format!("({})", other.text())
+    };
+    Some(if negated {
+        format!("{other} is not none")
+    } else {
+        format!("{other} is none")
+    })
+}
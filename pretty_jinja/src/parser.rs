@@ -1,18 +1,22 @@
-use crate::syntax::{JinjaLanguage, SyntaxKind};
+use crate::{
+    config::Dialect,
+    syntax::{JinjaLanguage, SyntaxKind},
+};
 use rowan::{GreenNode, GreenToken, NodeOrToken};
 use winnow::{
     Parser,
-    ascii::{line_ending, multispace1, take_escaped},
-    combinator::{alt, eof, opt, peek, repeat, terminated},
+    ascii::{multispace1, take_escaped},
+    combinator::{alt, eof, fail, not, opt, peek, repeat, terminated},
     error::{ContextError, ParseError},
-    stream::AsChar,
+    stream::{AsChar, Stateful},
     token::{any, none_of, one_of, take_while},
 };
 
 type SyntaxNode = rowan::SyntaxNode<JinjaLanguage>;
 type GreenElement = NodeOrToken<GreenNode, GreenToken>;
 type GreenResult = winnow::Result<GreenElement>;
-type Input<'s> = &'s str;
+/// Input of parsers with dialect as state.
+type Input<'s> = Stateful<&'s str, Dialect>;
 
 fn tok(kind: SyntaxKind, text: &str) -> GreenElement {
     NodeOrToken::Token(GreenToken::new(kind.into(), text))
@@ -25,6 +29,40 @@ where
     NodeOrToken::Node(GreenNode::new(kind.into(), children))
 }
 
+/// Run the parser only in the given dialects, otherwise fail.
+fn only<'s, O, P>(
+    dialects: &'static [Dialect],
+    mut parser: P,
+) -> impl Parser<Input<'s>, O, ContextError>
+where
+    P: Parser<Input<'s>, O, ContextError>,
+{
+    move |input: &mut Input<'s>| {
+        if dialects.contains(&input.state) {
+            parser.parse_next(input)
+        } else {
+            fail.parse_next(input)
+        }
+    }
+}
+
+/// Run the parser in dialects except the given ones, otherwise fail.
+fn unless<'s, O, P>(
+    dialects: &'static [Dialect],
+    mut parser: P,
+) -> impl Parser<Input<'s>, O, ContextError>
+where
+    P: Parser<Input<'s>, O, ContextError>,
+{
+    move |input: &mut Input<'s>| {
+        if dialects.contains(&input.state) {
+            fail.parse_next(input)
+        } else {
+            parser.parse_next(input)
+        }
+    }
+}
+
 fn is_ident_char(c: char) -> bool {
     c.is_ascii_alphanumeric() || c == '_' || !c.is_ascii()
 }
@@ -35,10 +73,19 @@ fn whitespace(input: &mut Input) -> GreenResult {
         .map(|text| tok(SyntaxKind::WHITESPACE, text))
 }
 
-fn bool(input: &mut Input) -> GreenResult {
-    terminated(alt(("true", "false")), peek(none_of(is_ident_char)))
+fn bom(input: &mut Input) -> GreenResult {
+    take_while(1.., '\u{feff}')
         .parse_next(input)
-        .map(|text| tok(SyntaxKind::BOOL, text))
+        .map(|text| tok(SyntaxKind::BOM, text))
+}
+
+fn bool(input: &mut Input) -> GreenResult {
+    terminated(
+        alt(("true", "false")),
+        peek(alt((none_of(is_ident_char).void(), eof.void()))),
+    )
+    .parse_next(input)
+    .map(|text| tok(SyntaxKind::BOOL, text))
 }
 
 fn ident(input: &mut Input) -> GreenResult {
@@ -59,7 +106,14 @@ fn number(input: &mut Input) -> GreenResult {
     (
         opt(one_of(['+', '-'])),
         (
-            (unsigned_dec, opt(('.', opt(unsigned_dec)))),
+            (
+                unsigned_dec,
+                // `1..2` is a range of Twig, not a float followed by `.`
+                opt((
+                    terminated('.', not(only(&[Dialect::Twig], '.'))),
+                    opt(unsigned_dec),
+                )),
+            ),
             opt((one_of(['e', 'E']), opt(one_of(['+', '-'])), unsigned_dec)),
         )
             .void(),
@@ -83,12 +137,20 @@ fn string(input: &mut Input) -> GreenResult {
         (
             '"',
             take_escaped(none_of(['"', '\\', '\n', '\r']), '\\', any),
-            alt(("\"", peek(line_ending), eof)),
+            alt(("\"", eof)),
         ),
         (
             '\'',
             take_escaped(none_of(['\'', '\\', '\n', '\r']), '\\', any),
-            alt(("'", peek(line_ending), eof)),
+            alt(("'", eof)),
+        ),
+        only(
+            &[Dialect::Tera],
+            (
+                '`',
+                take_escaped(none_of(['`', '\\', '\n', '\r']), '\\', any),
+                alt(("`", eof)),
+            ),
         ),
     ))
     .take()
@@ -96,8 +158,26 @@ fn string(input: &mut Input) -> GreenResult {
     .map(|text| tok(SyntaxKind::STRING, text))
 }
 
+/// Regex literal of Nunjucks, such as `r/abc/g`.
+fn regex(input: &mut Input) -> GreenResult {
+    (
+        "r/",
+        take_escaped(none_of(['/', '\\', '\n', '\r']), '\\', any),
+        '/',
+        take_while(0.., AsChar::is_alpha),
+    )
+        .take()
+        .parse_next(input)
+        .map(|text| tok(SyntaxKind::REGEX, text))
+}
+
 fn expr(input: &mut Input) -> GreenResult {
-    try_expr_if.parse_next(input)
+    alt((
+        only(&[Dialect::Twig], expr_arrow),
+        only(&[Dialect::Twig], try_expr_ternary),
+        try_expr_if,
+    ))
+    .parse_next(input)
 }
 
 fn expr_access(input: &mut Input) -> GreenResult {
@@ -106,45 +186,52 @@ fn expr_access(input: &mut Input) -> GreenResult {
         repeat::<_, _, Vec<_>, _, _>(
             0..,
             alt((
-                (opt(whitespace), '.', opt(whitespace), expr_term).map(
-                    |(ws_leading, _, ws, expr)| {
+                (
+                    opt(whitespace),
+                    ".",
+                    opt(whitespace),
+                    // index of Django like `a.0.b`, which shouldn't be parsed as float `0.`
+                    alt((only(&[Dialect::Django], expr_index), expr_term)),
+                )
+                    .map(|(ws_leading, dot, ws, expr)| {
                         let mut children = Vec::with_capacity(2);
                         if let Some(ws) = ws_leading {
                             children.push(ws);
                         }
-                        children.push(tok(SyntaxKind::DOT, "."));
+                        children.push(tok(SyntaxKind::DOT, dot));
                         if let Some(ws) = ws {
                             children.push(ws);
                         }
                         children.push(expr);
                         (SyntaxKind::EXPR_GET_ATTR, children)
-                    },
-                ),
+                    }),
                 (
                     opt(whitespace),
-                    '[',
+                    "[",
                     opt(whitespace),
                     expr,
                     opt(whitespace),
-                    ']',
+                    "]",
                 )
-                    .map(|(ws_leading, _, ws_before, expr, ws_after, _)| {
-                        let mut children = Vec::with_capacity(3);
-                        if let Some(ws) = ws_leading {
-                            children.push(ws);
-                        }
-                        children.push(tok(SyntaxKind::L_BRACKET, "["));
-                        if let Some(ws) = ws_before {
-                            children.push(ws);
-                        }
-                        children.push(expr);
-                        if let Some(ws) = ws_after {
-                            children.push(ws);
-                        }
-                        children.push(tok(SyntaxKind::R_BRACKET, "]"));
-                        (SyntaxKind::EXPR_GET_ITEM, children)
-                    }),
-                args.map(|args| (SyntaxKind::EXPR_CALL, args)),
+                    .map(
+                        |(ws_leading, l_bracket, ws_before, expr, ws_after, r_bracket)| {
+                            let mut children = Vec::with_capacity(3);
+                            if let Some(ws) = ws_leading {
+                                children.push(ws);
+                            }
+                            children.push(tok(SyntaxKind::L_BRACKET, l_bracket));
+                            if let Some(ws) = ws_before {
+                                children.push(ws);
+                            }
+                            children.push(expr);
+                            if let Some(ws) = ws_after {
+                                children.push(ws);
+                            }
+                            children.push(tok(SyntaxKind::R_BRACKET, r_bracket));
+                            (SyntaxKind::EXPR_GET_ITEM, children)
+                        },
+                    ),
+                unless(&[Dialect::Django], args).map(|args| (SyntaxKind::EXPR_CALL, args)),
             )),
         ),
     )
@@ -163,63 +250,133 @@ fn expr_access(input: &mut Input) -> GreenResult {
 fn args(input: &mut Input) -> winnow::Result<Vec<GreenElement>> {
     (
         opt(whitespace),
-        '(',
+        "(",
         repeat::<_, _, Vec<_>, _, _>(
             0..,
             (
                 opt(whitespace),
-                opt((ident, opt(whitespace), '=', opt(whitespace))),
+                opt((
+                    ident,
+                    opt(whitespace),
+                    // `=>` is arrow function of Twig
+                    terminated("=", not(one_of(['=', '>']))),
+                    opt(whitespace),
+                )),
                 expr,
                 alt((
-                    (opt(whitespace), ',').map(Some),
+                    (opt(whitespace), ",").map(Some),
                     peek((opt(whitespace), ')')).value(None),
                 )),
             ),
         ),
         opt(whitespace),
-        ')',
+        ")",
     )
         .parse_next(input)
-        .map(|(ws_leading, _, args, ws_after, _)| {
+        .map(|(ws_leading, l_paren, args, ws_after, r_paren)| {
             let mut children = Vec::with_capacity(2 + args.len() * 3);
             if let Some(ws) = ws_leading {
                 children.push(ws);
             }
-            children.push(tok(SyntaxKind::L_PAREN, "("));
+            children.push(tok(SyntaxKind::L_PAREN, l_paren));
             args.into_iter().for_each(|(ws_before, name, expr, comma)| {
                 if let Some(ws) = ws_before {
                     children.push(ws);
                 }
                 let mut arg_children = Vec::with_capacity(3);
-                if let Some((ident, ws_before, _, ws_after)) = name {
+                if let Some((ident, ws_before, eq, ws_after)) = name {
                     arg_children.push(ident);
                     if let Some(ws) = ws_before {
                         arg_children.push(ws);
                     }
-                    arg_children.push(tok(SyntaxKind::EQ, "="));
+                    arg_children.push(tok(SyntaxKind::EQ, eq));
                     if let Some(ws) = ws_after {
                         arg_children.push(ws);
                     }
                 }
                 arg_children.push(expr);
                 children.push(node(SyntaxKind::ARG, arg_children));
-                if let Some((ws, _)) = comma {
+                if let Some((ws, comma)) = comma {
                     if let Some(ws) = ws {
                         children.push(ws);
                     }
-                    children.push(tok(SyntaxKind::COMMA, ","));
+                    children.push(tok(SyntaxKind::COMMA, comma));
                 }
             });
             if let Some(ws) = ws_after {
                 children.push(ws);
             }
-            children.push(tok(SyntaxKind::R_PAREN, ")"));
+            children.push(tok(SyntaxKind::R_PAREN, r_paren));
             children
         })
 }
 
+/// Arrow function of Twig, such as `x => x.id` or `(v, k) => v ~ k`.
+fn expr_arrow(input: &mut Input) -> GreenResult {
+    (
+        alt((
+            ident.map(|ident| vec![ident]),
+            (
+                "(",
+                repeat::<_, _, Vec<_>, _, _>(
+                    0..,
+                    (
+                        opt(whitespace),
+                        ident,
+                        alt((
+                            (opt(whitespace), ",").map(Some),
+                            peek((opt(whitespace), ')')).value(None),
+                        )),
+                    ),
+                ),
+                opt(whitespace),
+                ")",
+            )
+                .map(|(l_paren, params, ws_trailing, r_paren)| {
+                    let mut children = Vec::with_capacity(2 + params.len() * 3);
+                    children.push(tok(SyntaxKind::L_PAREN, l_paren));
+                    params.into_iter().for_each(|(ws_before, ident, comma)| {
+                        if let Some(ws) = ws_before {
+                            children.push(ws);
+                        }
+                        children.push(ident);
+                        if let Some((ws, comma)) = comma {
+                            if let Some(ws) = ws {
+                                children.push(ws);
+                            }
+                            children.push(tok(SyntaxKind::COMMA, comma));
+                        }
+                    });
+                    if let Some(ws) = ws_trailing {
+                        children.push(ws);
+                    }
+                    children.push(tok(SyntaxKind::R_PAREN, r_paren));
+                    children
+                }),
+        )),
+        opt(whitespace),
+        "=>",
+        opt(whitespace),
+        expr,
+    )
+        .parse_next(input)
+        .map(|(mut params, ws_before, arrow, ws_after, body)| {
+            let mut children = Vec::with_capacity(params.len() + 4);
+            children.append(&mut params);
+            if let Some(ws) = ws_before {
+                children.push(ws);
+            }
+            children.push(tok(SyntaxKind::OPERATOR, arrow));
+            if let Some(ws) = ws_after {
+                children.push(ws);
+            }
+            children.push(body);
+            node(SyntaxKind::EXPR_ARROW, children)
+        })
+}
+
 fn expr_bin(input: &mut Input) -> GreenResult {
-    expr_bin_or.parse_next(input)
+    expr_bin_coalesce.parse_next(input)
 }
 fn expr_bin_common<'s, P1, P2>(
     operand: P1,
@@ -262,27 +419,63 @@ fn expr_bin_pow(input: &mut Input) -> GreenResult {
     expr_bin_common(expr_unary, "**").parse_next(input)
 }
 fn expr_bin_mul(input: &mut Input) -> GreenResult {
-    expr_bin_common(expr_bin_pow, alt(("*", "/", "//", "%"))).parse_next(input)
+    expr_bin_common(expr_bin_pow, alt(("*", "//", "/", "%"))).parse_next(input)
 }
 fn expr_bin_add(input: &mut Input) -> GreenResult {
     expr_bin_common(expr_bin_mul, alt(("+", "-"))).parse_next(input)
 }
+fn expr_bin_range(input: &mut Input) -> GreenResult {
+    expr_bin_common(expr_bin_add, only(&[Dialect::Twig], "..")).parse_next(input)
+}
 fn expr_bin_cmp(input: &mut Input) -> GreenResult {
     expr_bin_common(
-        expr_bin_add,
+        expr_bin_range,
         alt((
+            only(&[Dialect::Nunjucks], alt(("===", "!=="))),
+            only(
+                &[Dialect::Twig],
+                alt((
+                    "<=>",
+                    terminated(
+                        alt((
+                            (alt(("starts", "ends")), multispace1, "with").take(),
+                            "matches",
+                        )),
+                        peek(none_of(is_ident_char)),
+                    ),
+                )),
+            ),
             "==",
             "!=",
             ('>', opt('=')).take(),
             ('<', opt('=')).take(),
-            terminated("in", peek(none_of(is_ident_char))),
+            terminated(
+                alt((("not", multispace1, "in").take(), "in")),
+                peek(none_of(is_ident_char)),
+            ),
         )),
     )
     .parse_next(input)
 }
+fn expr_bin_bit_and(input: &mut Input) -> GreenResult {
+    expr_bin_common(expr_bin_cmp, bit_operator("b-and")).parse_next(input)
+}
+fn expr_bin_bit_xor(input: &mut Input) -> GreenResult {
+    expr_bin_common(expr_bin_bit_and, bit_operator("b-xor")).parse_next(input)
+}
+fn expr_bin_bit_or(input: &mut Input) -> GreenResult {
+    expr_bin_common(expr_bin_bit_xor, bit_operator("b-or")).parse_next(input)
+}
+/// Bitwise operator of Twig, such as `b-and`.
+fn bit_operator<'s>(operator: &'static str) -> impl Parser<Input<'s>, &'s str, ContextError> {
+    only(
+        &[Dialect::Twig],
+        terminated(operator, peek(none_of(is_ident_char))),
+    )
+}
 fn expr_bin_and(input: &mut Input) -> GreenResult {
     expr_bin_common(
-        expr_bin_cmp,
+        expr_bin_bit_or,
         terminated("and", peek(none_of(is_ident_char))),
     )
     .parse_next(input)
@@ -290,6 +483,9 @@ fn expr_bin_and(input: &mut Input) -> GreenResult {
 fn expr_bin_or(input: &mut Input) -> GreenResult {
     expr_bin_common(expr_bin_and, terminated("or", peek(none_of(is_ident_char)))).parse_next(input)
 }
+fn expr_bin_coalesce(input: &mut Input) -> GreenResult {
+    expr_bin_common(expr_bin_or, only(&[Dialect::Twig], alt(("??", "?:")))).parse_next(input)
+}
 
 fn expr_concat(input: &mut Input) -> GreenResult {
     (
@@ -298,7 +494,7 @@ fn expr_concat(input: &mut Input) -> GreenResult {
             0..,
             (
                 opt(whitespace),
-                '~'.map(|_| tok(SyntaxKind::OPERATOR, "~")),
+                '~'.take().map(|text| tok(SyntaxKind::OPERATOR, text)),
                 opt(whitespace),
                 try_expr_test,
             ),
@@ -330,31 +526,31 @@ fn expr_concat(input: &mut Input) -> GreenResult {
 
 fn expr_dict(input: &mut Input) -> GreenResult {
     (
-        '{',
+        "{",
         repeat::<_, _, Vec<_>, _, _>(
             0..,
             (
                 opt(whitespace),
                 expr,
                 opt(whitespace),
-                ':',
+                ":",
                 opt(whitespace),
                 expr,
                 alt((
-                    (opt(whitespace), ',').map(Some),
+                    (opt(whitespace), ",").map(Some),
                     peek((opt(whitespace), '}')).value(None),
                 )),
             ),
         ),
         opt(whitespace),
-        '}',
+        "}",
     )
         .parse_next(input)
-        .map(|(_, entries, ws_trailing, _)| {
+        .map(|(l_brace, entries, ws_trailing, r_brace)| {
             let mut children = Vec::with_capacity(2 + entries.len() * 3);
-            children.push(tok(SyntaxKind::L_BRACE, "{"));
+            children.push(tok(SyntaxKind::L_BRACE, l_brace));
             entries.into_iter().for_each(
-                |(ws_leading, key, ws_before, _, ws_after, value, comma)| {
+                |(ws_leading, key, ws_before, colon, ws_after, value, comma)| {
                     if let Some(ws) = ws_leading {
                         children.push(ws);
                     }
@@ -363,24 +559,24 @@ fn expr_dict(input: &mut Input) -> GreenResult {
                     if let Some(ws) = ws_before {
                         entry_children.push(ws);
                     }
-                    entry_children.push(tok(SyntaxKind::COLON, ":"));
+                    entry_children.push(tok(SyntaxKind::COLON, colon));
                     if let Some(ws) = ws_after {
                         entry_children.push(ws);
                     }
                     entry_children.push(value);
                     children.push(node(SyntaxKind::EXPR_DICT_ITEM, entry_children));
-                    if let Some((ws, _)) = comma {
+                    if let Some((ws, comma)) = comma {
                         if let Some(ws) = ws {
                             children.push(ws);
                         }
-                        children.push(tok(SyntaxKind::COMMA, ","));
+                        children.push(tok(SyntaxKind::COMMA, comma));
                     }
                 },
             );
             if let Some(ws) = ws_trailing {
                 children.push(ws);
             }
-            children.push(tok(SyntaxKind::R_BRACE, "}"));
+            children.push(tok(SyntaxKind::R_BRACE, r_brace));
             node(SyntaxKind::EXPR_DICT, children)
         })
 }
@@ -404,21 +600,9 @@ fn filters<'s>() -> impl Parser<Input<'s>, Vec<GreenElement>, ContextError> {
         0..,
         (
             opt(whitespace),
-            '|'.map(|_| tok(SyntaxKind::OPERATOR, "|")),
+            '|'.take().map(|text| tok(SyntaxKind::OPERATOR, text)),
             opt(whitespace),
-            (ident, opt((opt(whitespace), args))).map(|(ident, args)| {
-                let mut children = Vec::with_capacity(2);
-                children.push(node(SyntaxKind::EXPR_IDENT, [ident]));
-                if let Some((ws, mut args)) = args {
-                    if let Some(ws) = ws {
-                        children.push(ws);
-                    }
-                    children.append(&mut args);
-                    node(SyntaxKind::EXPR_CALL, children)
-                } else {
-                    node(SyntaxKind::EXPR_IDENT, children)
-                }
-            }),
+            filter,
         ),
     )
     .fold(
@@ -437,12 +621,100 @@ fn filters<'s>() -> impl Parser<Input<'s>, Vec<GreenElement>, ContextError> {
     )
 }
 
+/// Filter name with optional arguments.
+fn filter(input: &mut Input) -> GreenResult {
+    alt((
+        only(&[Dialect::Django], filter_with_colon),
+        filter_with_args,
+    ))
+    .parse_next(input)
+}
+fn filter_with_args(input: &mut Input) -> GreenResult {
+    (
+        ident,
+        opt((opt(whitespace), unless(&[Dialect::Django], args))),
+    )
+        .parse_next(input)
+        .map(|(ident, args)| {
+            let mut children = Vec::with_capacity(2);
+            children.push(node(SyntaxKind::EXPR_IDENT, [ident]));
+            if let Some((ws, mut args)) = args {
+                if let Some(ws) = ws {
+                    children.push(ws);
+                }
+                children.append(&mut args);
+                node(SyntaxKind::EXPR_CALL, children)
+            } else {
+                node(SyntaxKind::EXPR_IDENT, children)
+            }
+        })
+}
+
+/// Filter of Django with single argument, such as `default:"x"`.
+fn filter_with_colon(input: &mut Input) -> GreenResult {
+    (ident, ":", expr_access)
+        .parse_next(input)
+        .map(|(ident, colon, arg)| {
+            node(
+                SyntaxKind::EXPR_CALL,
+                [
+                    node(SyntaxKind::EXPR_IDENT, [ident]),
+                    tok(SyntaxKind::COLON, colon),
+                    node(SyntaxKind::ARG, [arg]),
+                ],
+            )
+        })
+}
+
 fn expr_ident(input: &mut Input) -> GreenResult {
     ident
         .parse_next(input)
         .map(|token| node(SyntaxKind::EXPR_IDENT, [token]))
 }
 
+/// Conditional expression of Twig like `a ? b : c`, whose else branch can be omitted.
+fn try_expr_ternary(input: &mut Input) -> GreenResult {
+    (
+        expr_bin,
+        opt((
+            opt(whitespace),
+            // `??` and `?:` are binary operators
+            terminated("?", not(one_of(['?', ':']))),
+            opt(whitespace),
+            expr,
+            opt((opt(whitespace), ":", opt(whitespace), expr)),
+        )),
+    )
+        .parse_next(input)
+        .map(|(expr, rest)| {
+            if let Some((ws_before, question, ws_after, consequent, alternate)) = rest {
+                let mut children = Vec::with_capacity(9);
+                children.push(expr);
+                if let Some(ws) = ws_before {
+                    children.push(ws);
+                }
+                children.push(tok(SyntaxKind::OPERATOR, question));
+                if let Some(ws) = ws_after {
+                    children.push(ws);
+                }
+                children.push(consequent);
+                if let Some((ws_before, colon, ws_after, alternate)) = alternate {
+                    if let Some(ws) = ws_before {
+                        children.push(ws);
+                    }
+                    children.push(tok(SyntaxKind::OPERATOR, colon));
+                    if let Some(ws) = ws_after {
+                        children.push(ws);
+                    }
+                    children.push(alternate);
+                }
+                node(SyntaxKind::EXPR_TERNARY, children)
+            } else {
+                expr
+            }
+        })
+}
+
 fn try_expr_if(input: &mut Input) -> GreenResult {
     (
         expr_bin,
@@ -452,15 +724,15 @@ fn try_expr_if(input: &mut Input) -> GreenResult {
     )
         .parse_next(input)
         .map(|(expr, rest)| {
-            if let Some((ws1, _, ws2, cond, ws3, _, ws4, else_expr)) = rest {
+            if let Some((ws1, if_kw, ws2, cond, ws3, else_kw, ws4, else_expr)) = rest {
                 let mut children = vec![expr];
                 children.reserve(7);
                 children.push(ws1);
-                children.push(tok(SyntaxKind::KEYWORD, "if"));
+                children.push(tok(SyntaxKind::KEYWORD, if_kw));
                 children.push(ws2);
                 children.push(cond);
                 children.push(ws3);
-                children.push(tok(SyntaxKind::KEYWORD, "else"));
+                children.push(tok(SyntaxKind::KEYWORD, else_kw));
                 children.push(ws4);
                 children.push(else_expr);
                 node(SyntaxKind::EXPR_IF, children)
@@ -472,51 +744,103 @@ fn try_expr_if(input: &mut Input) -> GreenResult {
 
 fn expr_list(input: &mut Input) -> GreenResult {
     (
-        '[',
+        "[",
         repeat::<_, _, Vec<_>, _, _>(
             0..,
             (
                 opt(whitespace),
                 expr,
                 alt((
-                    (opt(whitespace), ',').map(Some),
+                    (opt(whitespace), ",").map(Some),
                     peek((opt(whitespace), ']')).value(None),
                 )),
             ),
         ),
         opt(whitespace),
-        ']',
+        "]",
     )
         .parse_next(input)
-        .map(|(_, elements, ws_trailing, _)| {
+        .map(|(l_bracket, elements, ws_trailing, r_bracket)| {
             let mut children = Vec::with_capacity(2 + elements.len() * 3);
-            children.push(tok(SyntaxKind::L_BRACKET, "["));
+            children.push(tok(SyntaxKind::L_BRACKET, l_bracket));
             elements.into_iter().for_each(|(ws_before, expr, comma)| {
                 if let Some(ws) = ws_before {
                     children.push(ws);
                 }
                 children.push(expr);
-                if let Some((ws, _)) = comma {
+                if let Some((ws, comma)) = comma {
                     if let Some(ws) = ws {
                         children.push(ws);
                     }
-                    children.push(tok(SyntaxKind::COMMA, ","));
+                    children.push(tok(SyntaxKind::COMMA, comma));
                 }
             });
             if let Some(ws) = ws_trailing {
                 children.push(ws);
             }
-            children.push(tok(SyntaxKind::R_BRACKET, "]"));
+            children.push(tok(SyntaxKind::R_BRACKET, r_bracket));
             node(SyntaxKind::EXPR_LIST, children)
         })
 }
 
+fn expr_index(input: &mut Input) -> GreenResult {
+    terminated(
+        take_while(1.., AsChar::is_dec_digit),
+        peek(alt((none_of(is_ident_char).void(), eof.void()))),
+    )
+    .parse_next(input)
+    .map(|text| node(SyntaxKind::EXPR_LITERAL, [tok(SyntaxKind::NUMBER, text)]))
+}
+
 fn expr_literal(input: &mut Input) -> GreenResult {
-    alt((bool, number, string))
+    alt((bool, number, string, only(&[Dialect::Nunjucks], regex)))
         .parse_next(input)
         .map(|token| node(SyntaxKind::EXPR_LITERAL, [token]))
 }
 
+/// Macro with namespace of Tera, such as `macros::input`.
+fn expr_path(input: &mut Input) -> GreenResult {
+    (
+        expr_ident,
+        repeat::<_, _, Vec<_>, _, _>(1.., (opt(whitespace), "::", opt(whitespace), expr_ident)),
+    )
+        .parse_next(input)
+        .map(|(namespace, segments)| {
+            let mut children = Vec::with_capacity(1 + segments.len() * 4);
+            children.push(namespace);
+            segments
+                .into_iter()
+                .for_each(|(ws_before, colons, ws_after, name)| {
+                    if let Some(ws) = ws_before {
+                        children.push(ws);
+                    }
+                    children.push(tok(SyntaxKind::DOUBLE_COLON, colons));
+                    if let Some(ws) = ws_after {
+                        children.push(ws);
+                    }
+                    children.push(name);
+                });
+            node(SyntaxKind::EXPR_PATH, children)
+        })
+}
+
+/// Translated string of Django, such as `_("text")`, which is the only call in Django.
+fn expr_translation(input: &mut Input) -> GreenResult {
+    ("_", "(", string, ")")
+        .parse_next(input)
+        .map(|(callee, l_paren, string, r_paren)| {
+            node(
+                SyntaxKind::EXPR_CALL,
+                [
+                    node(SyntaxKind::EXPR_IDENT, [tok(SyntaxKind::IDENT, callee)]),
+                    tok(SyntaxKind::L_PAREN, l_paren),
+                    node(SyntaxKind::ARG, [node(SyntaxKind::EXPR_LITERAL, [string])]),
+                    tok(SyntaxKind::R_PAREN, r_paren),
+                ],
+            )
+        })
+}
+
 fn expr_paren(input: &mut Input) -> GreenResult {
     ("(", opt(whitespace), expr, opt(whitespace), ")")
         .parse_next(input)
@@ -538,6 +862,8 @@ fn expr_paren(input: &mut Input) -> GreenResult {
 fn expr_term(input: &mut Input) -> GreenResult {
     alt((
         expr_literal,
+        only(&[Dialect::Tera], expr_path),
+        only(&[Dialect::Django], expr_translation),
         expr_ident,
         expr_paren,
         expr_list,
@@ -552,21 +878,29 @@ fn try_expr_test(input: &mut Input) -> GreenResult {
         expr_filter,
         opt((
             opt(whitespace),
-            "is",
+            (
+                "is",
+                opt((multispace1, "not", peek(none_of(is_ident_char)))),
+            )
+                .take(),
             peek(none_of(is_ident_char)),
             opt(whitespace),
-            alt((expr_call_single_arg_for_expr_test, expr_access)),
+            alt((
+                only(&[Dialect::Twig], expr_twig_test),
+                unless(&[Dialect::Django], expr_call_single_arg_for_expr_test),
+                expr_access,
+            )),
         )),
     )
         .parse_next(input)
         .map(|(expr, test)| {
             let mut children = Vec::with_capacity(5);
-            if let Some((ws_before, _, _, ws_after, test)) = test {
+            if let Some((ws_before, operator, _, ws_after, test)) = test {
                 children.push(expr);
                 if let Some(ws) = ws_before {
                     children.push(ws);
                 }
-                children.push(tok(SyntaxKind::OPERATOR, "is"));
+                children.push(tok(SyntaxKind::OPERATOR, operator));
                 if let Some(ws) = ws_after {
                     children.push(ws);
                 }
@@ -577,6 +911,30 @@ fn try_expr_test(input: &mut Input) -> GreenResult {
             }
         })
 }
+/// Tests of Twig whose names have multiple words, like `same as(b)` and `divisible by(3)`.
+fn expr_twig_test(input: &mut Input) -> GreenResult {
+    (
+        alt((
+            ("same", multispace1, "as"),
+            ("divisible", multispace1, "by"),
+        ))
+        .take(),
+        not(one_of(is_ident_char)),
+        opt(args),
+    )
+        .parse_next(input)
+        .map(|(name, _, args)| {
+            let name = node(SyntaxKind::EXPR_IDENT, [tok(SyntaxKind::IDENT, name)]);
+            if let Some(mut args) = args {
+                let mut children = Vec::with_capacity(1 + args.len());
+                children.push(name);
+                children.append(&mut args);
+                node(SyntaxKind::EXPR_CALL, children)
+            } else {
+                name
+            }
+        })
+}
 fn expr_call_single_arg_for_expr_test(input: &mut Input) -> GreenResult {
     (expr_term, whitespace, peek(none_of('(')), expr_access)
         .parse_next(input)
@@ -590,20 +948,20 @@ fn expr_call_single_arg_for_expr_test(input: &mut Input) -> GreenResult {
 
 fn expr_tuple(input: &mut Input) -> GreenResult {
     (
-        '(',
+        "(",
         repeat::<_, _, Vec<_>, _, _>(
             0..,
             (
                 opt(whitespace),
                 expr,
                 alt((
-                    (opt(whitespace), ',').map(Some),
+                    (opt(whitespace), ",").map(Some),
                     peek((opt(whitespace), ')')).value(None),
                 )),
             ),
         ),
         opt(whitespace),
-        ')',
+        ")",
     )
         .verify(|(_, items, _, _)| {
             if let Some((_, _, comma)) = items.first() {
@@ -613,25 +971,25 @@ fn expr_tuple(input: &mut Input) -> GreenResult {
             }
         })
         .parse_next(input)
-        .map(|(_, items, ws_trailing, _)| {
+        .map(|(l_paren, items, ws_trailing, r_paren)| {
             let mut children = Vec::with_capacity(2 + items.len() * 3);
-            children.push(tok(SyntaxKind::L_PAREN, "("));
+            children.push(tok(SyntaxKind::L_PAREN, l_paren));
             items.into_iter().for_each(|(ws_before, expr, comma)| {
                 if let Some(ws) = ws_before {
                     children.push(ws);
                 }
                 children.push(expr);
-                if let Some((ws, _)) = comma {
+                if let Some((ws, comma)) = comma {
                     if let Some(ws) = ws {
                         children.push(ws);
                     }
-                    children.push(tok(SyntaxKind::COMMA, ","));
+                    children.push(tok(SyntaxKind::COMMA, comma));
                 }
             });
             if let Some(ws) = ws_trailing {
                 children.push(ws);
             }
-            children.push(tok(SyntaxKind::R_PAREN, ")"));
+            children.push(tok(SyntaxKind::R_PAREN, r_paren));
             node(SyntaxKind::EXPR_TUPLE, children)
         })
 }
@@ -659,10 +1017,13 @@ fn expr_unary_not(input: &mut Input) -> GreenResult {
 }
 
 fn root_expr(input: &mut Input) -> winnow::Result<GreenNode> {
-    (opt(whitespace), expr, opt(whitespace))
+    (opt(bom), opt(whitespace), expr, opt(whitespace))
         .parse_next(input)
-        .map(|(ws_before, expr, ws_after)| {
-            let mut children = Vec::with_capacity(3);
+        .map(|(bom, ws_before, expr, ws_after)| {
+            let mut children = Vec::with_capacity(4);
+            if let Some(bom) = bom {
+                children.push(bom);
+            }
             if let Some(ws) = ws_before {
                 children.push(ws);
             }
@@ -674,19 +1035,25 @@ fn root_expr(input: &mut Input) -> winnow::Result<GreenNode> {
         })
 }
 
-pub(crate) fn parse_expr(code: &str) -> Result<SyntaxNode, ParseError<Input<'_>, ContextError>> {
-    let code = code.trim_start_matches('\u{feff}');
-    root_expr.parse(code).map(SyntaxNode::new_root)
+pub(crate) fn parse_expr(
+    code: &str,
+    dialect: Dialect,
+) -> Result<SyntaxNode, ParseError<Input<'_>, ContextError>> {
+    root_expr
+        .parse(Stateful {
+            input: code,
+            state: dialect,
+        })
+        .map(SyntaxNode::new_root)
 }
 
 fn stmt(input: &mut Input) -> GreenResult {
     alt((
         stmt_for,
-        stmt_macro,
-        stmt_call,
-        stmt_set,
+        unless(&[Dialect::Django], alt((stmt_macro, stmt_call, stmt_set))),
         stmt_filter,
-        stmt_with,
+        // `{% with %}` of Django has multiple assignments without commas
+        unless(&[Dialect::Django], stmt_with),
         stmt_unknown,
     ))
     .parse_next(input)
@@ -698,45 +1065,45 @@ fn stmt_call(input: &mut Input) -> GreenResult {
         alt((
             (
                 opt(whitespace),
-                '(',
+                "(",
                 repeat::<_, _, Vec<_>, _, _>(
                     0..,
                     (
                         opt(whitespace),
                         ident,
                         alt((
-                            (opt(whitespace), ',').map(Some),
+                            (opt(whitespace), ",").map(Some),
                             peek((opt(whitespace), ')')).value(None),
                         )),
                     ),
                 ),
                 opt(whitespace),
-                ')',
+                ")",
                 opt(whitespace),
             )
-                .map(|(ws1, _, names, ws2, _, ws3)| {
+                .map(|(ws1, l_paren, names, ws2, r_paren, ws3)| {
                     let mut children = Vec::with_capacity(3);
                     if let Some(ws) = ws1 {
                         children.push(ws);
                     }
                     let mut params_children = Vec::with_capacity(1 + names.len() * 3);
-                    params_children.push(tok(SyntaxKind::L_PAREN, "("));
+                    params_children.push(tok(SyntaxKind::L_PAREN, l_paren));
                     names.into_iter().for_each(|(ws_before, ident, comma)| {
                         if let Some(ws) = ws_before {
                             params_children.push(ws);
                         }
                         params_children.push(node(SyntaxKind::PARAM, [ident]));
-                        if let Some((ws, _)) = comma {
+                        if let Some((ws, comma)) = comma {
                             if let Some(ws) = ws {
                                 params_children.push(ws);
                             }
-                            params_children.push(tok(SyntaxKind::COMMA, ","));
+                            params_children.push(tok(SyntaxKind::COMMA, comma));
                         }
                     });
                     if let Some(ws) = ws2 {
                         params_children.push(ws);
                     }
-                    params_children.push(tok(SyntaxKind::R_PAREN, ")"));
+                    params_children.push(tok(SyntaxKind::R_PAREN, r_paren));
                     children.push(node(SyntaxKind::CALL_PARAMS, params_children));
                     if let Some(ws) = ws3 {
                         children.push(ws);
@@ -749,9 +1116,9 @@ fn stmt_call(input: &mut Input) -> GreenResult {
         args,
     )
         .parse_next(input)
-        .map(|(_, mut params, name, mut args)| {
+        .map(|(keyword, mut params, name, mut args)| {
             let mut children = Vec::with_capacity(2 + params.len() + args.len());
-            children.push(tok(SyntaxKind::KEYWORD, "call"));
+            children.push(tok(SyntaxKind::KEYWORD, keyword));
             children.append(&mut params);
             children.push(name);
             children.append(&mut args);
@@ -760,27 +1127,27 @@ fn stmt_call(input: &mut Input) -> GreenResult {
 }
 
 fn stmt_filter(input: &mut Input) -> GreenResult {
-    ("filter", whitespace, ident, opt(args))
+    ("filter", whitespace, filter, filters())
         .parse_next(input)
-        .map(|(_, ws, name, args)| {
-            let mut children =
-                Vec::with_capacity(3 + args.as_ref().map(|args| args.len()).unwrap_or_default());
-            children.push(tok(SyntaxKind::KEYWORD, "filter"));
+        .map(|(keyword, ws, filter, mut filters)| {
+            let mut children = Vec::with_capacity(3 + filters.len());
+            children.push(tok(SyntaxKind::KEYWORD, keyword));
             children.push(ws);
-            children.push(name);
-            if let Some(mut args) = args {
-                children.append(&mut args);
-            }
+            children.push(filter);
+            children.append(&mut filters);
             node(SyntaxKind::STMT_FILTER, children)
         })
 }
 
 fn stmt_for(input: &mut Input) -> GreenResult {
     (
-        "for",
+        alt((
+            "for",
+            only(&[Dialect::Nunjucks], alt(("asyncEach", "asyncAll"))),
+        )),
         whitespace,
         ident,
-        repeat::<_, _, Vec<_>, _, _>(0.., (opt(whitespace), ',', opt(whitespace), ident)),
+        repeat::<_, _, Vec<_>, _, _>(0.., (opt(whitespace), ",", opt(whitespace), ident)),
         whitespace,
         "in",
         whitespace,
@@ -789,33 +1156,39 @@ fn stmt_for(input: &mut Input) -> GreenResult {
             whitespace,
             alt((
                 ("if", whitespace, expr)
-                    .map(|(_, ws, expr)| vec![tok(SyntaxKind::KEYWORD, "if"), ws, expr]),
-                word.verify(|text: &str| text == "recursive")
-                    .map(|text| vec![tok(SyntaxKind::KEYWORD, text)]),
+                    .map(|(keyword, ws, expr)| vec![tok(SyntaxKind::KEYWORD, keyword), ws, expr]),
+                alt((
+                    word.verify(|text: &str| text == "recursive"),
+                    only(
+                        &[Dialect::Django],
+                        word.verify(|text: &str| text == "reversed"),
+                    ),
+                ))
+                .map(|text| vec![tok(SyntaxKind::KEYWORD, text)]),
             )),
         )),
     )
         .parse_next(input)
         .map(
-            |(_, ws1, fst_binding, rest_bindings, ws2, _, ws3, expr, extra)| {
+            |(for_kw, ws1, fst_binding, rest_bindings, ws2, in_kw, ws3, expr, extra)| {
                 let mut children = Vec::with_capacity(7 + rest_bindings.len() * 3);
-                children.push(tok(SyntaxKind::KEYWORD, "for"));
+                children.push(tok(SyntaxKind::KEYWORD, for_kw));
                 children.push(ws1);
                 children.push(fst_binding);
                 rest_bindings
                     .into_iter()
-                    .for_each(|(ws_before, _, ws_after, ident)| {
+                    .for_each(|(ws_before, comma, ws_after, ident)| {
                         if let Some(ws) = ws_before {
                             children.push(ws);
                         }
-                        children.push(tok(SyntaxKind::COMMA, ","));
+                        children.push(tok(SyntaxKind::COMMA, comma));
                         if let Some(ws) = ws_after {
                             children.push(ws);
                         }
                         children.push(ident);
                     });
                 children.push(ws2);
-                children.push(tok(SyntaxKind::KEYWORD, "in"));
+                children.push(tok(SyntaxKind::KEYWORD, in_kw));
                 children.push(ws3);
                 children.push(expr);
                 if let Some((ws, mut extra)) = extra {
@@ -833,60 +1206,75 @@ fn stmt_macro(input: &mut Input) -> GreenResult {
         whitespace,
         ident,
         opt(whitespace),
-        '(',
+        "(",
         repeat::<_, _, Vec<_>, _, _>(
             0..,
             (
                 opt(whitespace),
                 param,
                 alt((
-                    (opt(whitespace), ',').map(Some),
+                    (opt(whitespace), ",").map(Some),
                     peek((opt(whitespace), ')')).value(None),
                 )),
             ),
         ),
         opt(whitespace),
-        ')',
+        ")",
     )
         .parse_next(input)
-        .map(|(_, ws1, name, ws2, _, params, ws3, _)| {
+        .map(|(keyword, ws1, name, ws2, l_paren, params, ws3, r_paren)| {
             let mut children = Vec::with_capacity(5 + params.len() * 3);
-            children.push(tok(SyntaxKind::KEYWORD, "macro"));
+            children.push(tok(SyntaxKind::KEYWORD, keyword));
             children.push(ws1);
             children.push(name);
             if let Some(ws) = ws2 {
                 children.push(ws);
             }
-            children.push(tok(SyntaxKind::L_PAREN, "("));
+            children.push(tok(SyntaxKind::L_PAREN, l_paren));
             params.into_iter().for_each(|(ws, param, comma)| {
                 if let Some(ws) = ws {
                     children.push(ws);
                 }
                 children.push(param);
-                if let Some((ws, _)) = comma {
+                if let Some((ws, comma)) = comma {
                     if let Some(ws) = ws {
                         children.push(ws);
                     }
-                    children.push(tok(SyntaxKind::COMMA, ","));
+                    children.push(tok(SyntaxKind::COMMA, comma));
                 }
             });
             if let Some(ws) = ws3 {
                 children.push(ws);
             }
-            children.push(tok(SyntaxKind::R_PAREN, ")"));
+            children.push(tok(SyntaxKind::R_PAREN, r_paren));
             node(SyntaxKind::STMT_MACRO, children)
         })
 }
 fn param(input: &mut Input) -> GreenResult {
-    (ident, opt((opt(whitespace), '=', opt(whitespace), expr)))
+    alt((
+        (alt(("**", "*")), opt(whitespace), ident).map(|(operator, ws, name)| {
+            let mut children = Vec::with_capacity(3);
+            children.push(tok(SyntaxKind::OPERATOR, operator));
+            if let Some(ws) = ws {
+                children.push(ws);
+            }
+            children.push(name);
+            node(SyntaxKind::PARAM, children)
+        }),
+        param_with_default,
+    ))
+    .parse_next(input)
+}
+fn param_with_default(input: &mut Input) -> GreenResult {
+    (ident, opt((opt(whitespace), "=", opt(whitespace), expr)))
         .parse_next(input)
         .map(|(name, value)| {
             let mut children = vec![name];
-            if let Some((ws_before, _, ws_after, expr)) = value {
+            if let Some((ws_before, eq, ws_after, expr)) = value {
                 if let Some(ws) = ws_before {
                     children.push(ws);
                 }
-                children.push(tok(SyntaxKind::EQ, "="));
+                children.push(tok(SyntaxKind::EQ, eq));
                 if let Some(ws) = ws_after {
                     children.push(ws);
                 }
@@ -898,17 +1286,17 @@ fn param(input: &mut Input) -> GreenResult {
 
 fn stmt_set(input: &mut Input) -> GreenResult {
     (
-        "set",
+        alt((only(&[Dialect::Tera], "set_global"), "set")),
         whitespace,
         ident,
-        repeat::<_, _, Vec<_>, _, _>(0.., (opt(whitespace), ',', opt(whitespace), ident)),
+        repeat::<_, _, Vec<_>, _, _>(0.., (opt(whitespace), ",", opt(whitespace), ident)),
         alt((
-            (opt(whitespace), '=', opt(whitespace), expr).map(|(ws_before, _, ws_after, expr)| {
+            (opt(whitespace), "=", opt(whitespace), expr).map(|(ws_before, eq, ws_after, expr)| {
                 let mut children = Vec::with_capacity(4);
                 if let Some(ws) = ws_before {
                     children.push(ws);
                 }
-                children.push(tok(SyntaxKind::EQ, "="));
+                children.push(tok(SyntaxKind::EQ, eq));
                 if let Some(ws) = ws_after {
                     children.push(ws);
                 }
@@ -919,18 +1307,18 @@ fn stmt_set(input: &mut Input) -> GreenResult {
         )),
     )
         .parse_next(input)
-        .map(|(_, ws1, fst_name, names, mut rest)| {
+        .map(|(keyword, ws1, fst_name, names, mut rest)| {
             let mut children = Vec::with_capacity(3 + names.len() * 3 + rest.len());
-            children.push(tok(SyntaxKind::KEYWORD, "set"));
+            children.push(tok(SyntaxKind::KEYWORD, keyword));
             children.push(ws1);
             children.push(fst_name);
             names
                 .into_iter()
-                .for_each(|(ws_before, _, ws_after, ident)| {
+                .for_each(|(ws_before, comma, ws_after, ident)| {
                     if let Some(ws) = ws_before {
                         children.push(ws);
                     }
-                    children.push(tok(SyntaxKind::COMMA, ","));
+                    children.push(tok(SyntaxKind::COMMA, comma));
                     if let Some(ws) = ws_after {
                         children.push(ws);
                     }
@@ -944,7 +1332,14 @@ fn stmt_set(input: &mut Input) -> GreenResult {
 fn stmt_unknown(input: &mut Input) -> GreenResult {
     (
         word,
-        repeat::<_, _, Vec<_>, _, _>(0.., (opt(whitespace), expr, opt((opt(whitespace), ',')))),
+        repeat::<_, _, Vec<_>, _, _>(
+            0..,
+            (
+                opt(whitespace),
+                alt((only(&[Dialect::Django], stmt_kwarg), expr)),
+                opt((opt(whitespace), ",")),
+            ),
+        ),
     )
         .parse_next(input)
         .map(|(name, exprs)| {
@@ -955,41 +1350,66 @@ fn stmt_unknown(input: &mut Input) -> GreenResult {
                     children.push(ws);
                 }
                 children.push(expr);
-                if let Some((ws, _)) = comma {
+                if let Some((ws, comma)) = comma {
                     if let Some(ws) = ws {
                         children.push(ws);
                     }
-                    children.push(tok(SyntaxKind::COMMA, ","));
+                    children.push(tok(SyntaxKind::COMMA, comma));
                 }
             });
             node(SyntaxKind::STMT_UNKNOWN, children)
         })
 }
 
+/// Keyword argument of Django tags, such as `a=b` in `{% blocktrans with a=b %}`.
+fn stmt_kwarg(input: &mut Input) -> GreenResult {
+    (
+        ident,
+        opt(whitespace),
+        terminated("=", not('=')),
+        opt(whitespace),
+        expr,
+    )
+        .parse_next(input)
+        .map(|(ident, ws_before, eq, ws_after, expr)| {
+            let mut children = Vec::with_capacity(5);
+            children.push(ident);
+            if let Some(ws) = ws_before {
+                children.push(ws);
+            }
+            children.push(tok(SyntaxKind::EQ, eq));
+            if let Some(ws) = ws_after {
+                children.push(ws);
+            }
+            children.push(expr);
+            node(SyntaxKind::ARG, children)
+        })
+}
+
 fn stmt_with(input: &mut Input) -> GreenResult {
     (
         "with",
         whitespace,
         ident,
-        repeat::<_, _, Vec<_>, _, _>(0.., (opt(whitespace), ',', opt(whitespace), ident)),
+        repeat::<_, _, Vec<_>, _, _>(0.., (opt(whitespace), ",", opt(whitespace), ident)),
         opt(whitespace),
-        '=',
+        "=",
         opt(whitespace),
         expr,
     )
         .parse_next(input)
-        .map(|(_, ws1, fst_name, names, ws2, _, ws3, expr)| {
+        .map(|(keyword, ws1, fst_name, names, ws2, eq, ws3, expr)| {
             let mut children = Vec::with_capacity(7);
-            children.push(tok(SyntaxKind::KEYWORD, "with"));
+            children.push(tok(SyntaxKind::KEYWORD, keyword));
             children.push(ws1);
             children.push(fst_name);
             names
                 .into_iter()
-                .for_each(|(ws_before, _, ws_after, ident)| {
+                .for_each(|(ws_before, comma, ws_after, ident)| {
                     if let Some(ws) = ws_before {
                         children.push(ws);
                     }
-                    children.push(tok(SyntaxKind::COMMA, ","));
+                    children.push(tok(SyntaxKind::COMMA, comma));
                     if let Some(ws) = ws_after {
                         children.push(ws);
                     }
@@ -998,7 +1418,7 @@ fn stmt_with(input: &mut Input) -> GreenResult {
             if let Some(ws) = ws2 {
                 children.push(ws);
             }
-            children.push(tok(SyntaxKind::EQ, "="));
+            children.push(tok(SyntaxKind::EQ, eq));
             if let Some(ws) = ws3 {
                 children.push(ws);
             }
@@ -1008,10 +1428,13 @@ fn stmt_with(input: &mut Input) -> GreenResult {
 }
 
 fn root_stmt(input: &mut Input) -> winnow::Result<GreenNode> {
-    (opt(whitespace), stmt, opt(whitespace))
+    (opt(bom), opt(whitespace), stmt, opt(whitespace))
         .parse_next(input)
-        .map(|(ws_before, stmt, ws_after)| {
-            let mut children = Vec::with_capacity(3);
+        .map(|(bom, ws_before, stmt, ws_after)| {
+            let mut children = Vec::with_capacity(4);
+            if let Some(bom) = bom {
+                children.push(bom);
+            }
             if let Some(ws) = ws_before {
                 children.push(ws);
             }
@@ -1023,7 +1446,14 @@ fn root_stmt(input: &mut Input) -> winnow::Result<GreenNode> {
         })
 }
 
-pub(crate) fn parse_stmt(code: &str) -> Result<SyntaxNode, ParseError<Input<'_>, ContextError>> {
-    let code = code.trim_start_matches('\u{feff}');
-    root_stmt.parse(code).map(SyntaxNode::new_root)
+pub(crate) fn parse_stmt(
+    code: &str,
+    dialect: Dialect,
+) -> Result<SyntaxNode, ParseError<Input<'_>, ContextError>> {
+    root_stmt
+        .parse(Stateful {
+            input: code,
+            state: dialect,
+        })
+        .map(SyntaxNode::new_root)
 }
@@ -1,6 +1,6 @@
 use crate::{
-    config::{FormatOptions, LanguageOptions, TrailingComma},
-    syntax::{NodeOrToken, SyntaxKind, SyntaxNode},
+    config::{Dialect, FormatOptions, LanguageOptions, TrailingComma},
+    syntax::{NodeOrToken, SyntaxKind, SyntaxNode, SyntaxToken},
 };
 use rowan::{Direction, ast::support};
 use tiny_pretty::Doc;
@@ -20,10 +20,18 @@ struct Ctx<'b> {
     options: &'b LanguageOptions,
 }
 
+impl Ctx<'_> {
+    /// Django doesn't allow parens in tags, so they can't be added for clarity.
+    fn clarify_mixed_and_or(&self) -> bool {
+        self.options.clarify_mixed_and_or && self.options.dialect != Dialect::Django
+    }
+}
+
 fn print_node(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
     match node.kind() {
         SyntaxKind::ARG => print_arg(node, ctx),
         SyntaxKind::CALL_PARAMS => print_call_params(node, ctx),
+        SyntaxKind::EXPR_ARROW => print_expr_arrow(node, ctx),
         SyntaxKind::EXPR_BIN => print_expr_bin(node, ctx),
         SyntaxKind::EXPR_CALL => print_expr_call(node, ctx),
         SyntaxKind::EXPR_CONCAT => print_expr_concat(node, ctx),
@@ -37,6 +45,8 @@ fn print_node(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
         SyntaxKind::EXPR_LIST => print_expr_list(node, ctx),
         SyntaxKind::EXPR_LITERAL => print_expr_literal(node, ctx),
         SyntaxKind::EXPR_PAREN => print_expr_paren(node, ctx),
+        SyntaxKind::EXPR_PATH => print_without_whitespaces(node, ctx),
+        SyntaxKind::EXPR_TERNARY => print_expr_ternary(node, ctx),
         SyntaxKind::EXPR_TEST => print_expr_test(node, ctx),
         SyntaxKind::EXPR_TUPLE => print_expr_tuple(node, ctx),
         SyntaxKind::EXPR_UNARY => print_expr_unary(node, ctx),
@@ -55,7 +65,7 @@ fn print_node(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
 }
 
 fn print_arg(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
-    print_without_whitespaces(node, ctx)
+    print_with_kwarg_eq(node, ctx, Doc::nil)
 }
 
 fn print_call_params(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
@@ -65,19 +75,103 @@ fn print_call_params(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
         ctx.options.params_trailing_comma,
         ctx.options.params_prefer_single_line,
         ctx.options.params_paren_spacing,
+        false,
+    )
+}
+
+fn print_expr_arrow(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
+    Doc::list(
+        node.children_with_tokens()
+            .filter(|node_or_token| node_or_token.kind() != SyntaxKind::WHITESPACE)
+            .map(|node_or_token| match node_or_token {
+                NodeOrToken::Node(node) => print_node(&node, ctx),
+                NodeOrToken::Token(token) => match token.kind() {
+                    SyntaxKind::COMMA => Doc::text(", "),
+                    SyntaxKind::OPERATOR => Doc::text(" => "),
+                    _ => Doc::text(token.text().to_string()),
+                },
+            })
+            .collect(),
     )
 }
 
 fn print_expr_bin(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
-    let doc = print_expr_with_operator(node, ctx);
-    if node
-        .parent()
-        .is_some_and(|node| node.kind() == SyntaxKind::EXPR_BIN)
-    {
-        doc
-    } else {
-        doc.group()
-    }
+    use crate::config::OperatorLineBreak;
+
+    // Operators of the same precedence are flattened as siblings,
+    // so operands with lower precedence operators will break first.
+    let precedence = get_bin_operator_precedence(node);
+    let mut rights = vec![];
+    let mut current = node.clone();
+    let first = loop {
+        if let Some((operator, right)) =
+            support::token(&current, SyntaxKind::OPERATOR).zip(current.last_child())
+        {
+            rights.push((operator, right));
+        }
+        // Parens which will be removed are looked through,
+        // so the result is the same as formatting code without those parens.
+        match current
+            .first_child()
+            .map(|left| skip_redundant_parens(left, ctx))
+        {
+            Some(left)
+                if left.kind() == SyntaxKind::EXPR_BIN
+                    && get_bin_operator_precedence(&left) == precedence =>
+            {
+                current = left;
+            }
+            left => break left,
+        }
+    };
+
+    let needs_parens = |operand: &SyntaxNode| ctx.clarify_mixed_and_or() && is_and_in_or(operand);
+    let print_operand = |operand: &SyntaxNode| {
+        if needs_parens(operand) {
+            Doc::text("(")
+                .append(print_node(operand, ctx))
+                .append(Doc::text(")"))
+        } else {
+            print_node(operand, ctx)
+        }
+    };
+    first
+        .map(|first| {
+            // Indent broken operand of higher precedence so it won't look like siblings.
+            // Parenthesized operand is printed as the same as existing parens.
+            if first.kind() == SyntaxKind::EXPR_BIN && !needs_parens(&first) {
+                print_operand(&first).nest(ctx.indent_width)
+            } else {
+                print_operand(&first)
+            }
+        })
+        .unwrap_or_else(Doc::nil)
+        .append(
+            Doc::list(
+                rights
+                    .into_iter()
+                    .rev()
+                    .map(|(operator, right)| {
+                        // ranges of Twig are conventionally written without spaces, like `1..10`
+                        if operator.text() == ".." {
+                            return Doc::text("..").append(print_operand(&right));
+                        }
+                        let operator = print_operator(&operator);
+                        match ctx.options.operator_linebreak {
+                            OperatorLineBreak::Before => {
+                                Doc::line_or_space().append(operator).append(Doc::space())
+                            }
+                            OperatorLineBreak::After => {
+                                Doc::space().append(operator).append(Doc::line_or_space())
+                            }
+                        }
+                        .append(print_operand(&right))
+                    })
+                    .collect(),
+            )
+            .nest(ctx.indent_width),
+        )
+        .group()
 }
 
 fn print_expr_call(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
@@ -85,7 +179,17 @@ fn print_expr_call(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
         .first_child()
         .map(|node| print_node(&node, ctx))
         .unwrap_or_else(Doc::nil);
-    if support::token(node, SyntaxKind::L_PAREN).is_some() {
+    if support::token(node, SyntaxKind::COLON).is_some() {
+        // filter argument of Django like `default:"x"`
+        callee.append(Doc::text(":")).append(
+            node.first_child_by_kind(&|kind| kind == SyntaxKind::ARG)
+                .map(|node| print_arg(&node, ctx))
+                .unwrap_or_else(Doc::nil),
+        )
+    } else if ctx.options.dialect == Dialect::Django {
+        // translated string like `_("text")`
+        print_without_whitespaces(node, ctx)
+    } else if support::token(node, SyntaxKind::L_PAREN).is_some() {
         callee.append(print_comma_separated_with_delimiter(
             node.children_with_tokens()
                 .skip_while(|node_or_token| node_or_token.kind() != SyntaxKind::L_PAREN),
@@ -93,6 +197,7 @@ fn print_expr_call(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
             ctx.options.args_trailing_comma,
             ctx.options.args_prefer_single_line,
             ctx.options.args_paren_spacing,
+            is_always_broken_call(node, ctx),
         ))
     } else {
         callee
@@ -129,6 +234,24 @@ fn print_expr_call(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
     }
 }
 
+/// Calls that are conventionally written with one argument per line in specific ecosystem,
+/// such as `config()` of dbt models.
+fn is_always_broken_call(node: &SyntaxNode, ctx: &Ctx) -> bool {
+    let names: &[&str] = match ctx.options.dialect {
+        Dialect::Dbt => &["config"],
+        _ => return false,
+    };
+    node.first_child()
+        .filter(|callee| callee.kind() == SyntaxKind::EXPR_IDENT)
+        .and_then(|callee| support::token(&callee, SyntaxKind::IDENT))
+        .is_some_and(|ident| names.contains(&ident.text()))
+        && node
+            .children()
+            .filter(|child| child.kind() == SyntaxKind::ARG)
+            .nth(1)
+            .is_some()
+}
+
 fn print_expr_concat(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
     print_expr_with_operator(node, ctx).group()
 }
@@ -140,33 +263,141 @@ fn print_expr_dict(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
         ctx.options.expr_dict_trailing_comma,
         ctx.options.expr_dict_prefer_single_line,
         ctx.options.brace_spacing,
+        false,
     )
 }
 
 fn print_expr_dict_item(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
+    // Values aren't aligned at all if there's a key whose width can't be measured,
+    // otherwise the dict will be aligned partly.
+    let max_width = node
+        .parent()
+        .filter(|_| ctx.options.dict_align_values)
+        .and_then(|parent| {
+            parent
+                .children()
+                .filter(|item| item.kind() == SyntaxKind::EXPR_DICT_ITEM)
+                .map(|item| get_dict_key_width(&item))
+                .try_fold(0, |max, width| width.map(|width| max.max(width)))
+        });
+    let padding = max_width
+        .zip(get_dict_key_width(node))
+        .map(|(max_width, width)| {
+            Doc::flat_or_break(Doc::nil(), Doc::text(" ".repeat(max_width - width)))
+        })
+        .unwrap_or_else(Doc::nil);
+    let mut padding = Some(padding);
     Doc::list(
         node.children_with_tokens()
             .filter(|node_or_token| node_or_token.kind() != SyntaxKind::WHITESPACE)
             .map(|node_or_token| match node_or_token {
+                NodeOrToken::Node(node) if node.next_sibling().is_some() => {
+                    print_dict_key(&node, ctx)
+                }
                 NodeOrToken::Node(node) => print_node(&node, ctx),
-                NodeOrToken::Token(token) if token.kind() == SyntaxKind::COLON => Doc::text(": "),
+                NodeOrToken::Token(token) if token.kind() == SyntaxKind::COLON => {
+                    let mut doc = if ctx.options.dict_colon_space_before {
+                        Doc::text(" :")
+                    } else {
+                        Doc::text(":")
+                    };
+                    if ctx.options.dict_colon_space_after {
+                        doc = doc.append(Doc::space());
+                    }
+                    doc.append(padding.take().unwrap_or_else(Doc::nil))
+                }
                 NodeOrToken::Token(token) => Doc::text(token.text().to_string()),
             })
             .collect(),
     )
 }
+fn print_dict_key(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
+    use crate::config::Quotes;
+    let quote = match ctx.options.dict_key_quote {
+        Quotes::Preserve => return print_node(node, ctx),
+        Quotes::Double => '"',
+        Quotes::Single => '\'',
+    };
+    match support::token(node, SyntaxKind::STRING) {
+        Some(token) if node.kind() == SyntaxKind::EXPR_LITERAL => {
+            let text = token.text();
+            let content = text
+                .strip_prefix(['"', '\''])
+                .and_then(|rest| rest.strip_suffix(&text[..1]));
+            match content {
+                // double-quoted strings of Twig are interpolated, like `"#{name}"`
+                Some(content) if ctx.options.dialect == Dialect::Twig && content.contains("#{") => {
+                    Doc::text(text.to_string())
+                }
+                Some(content) if !content.contains(['"', '\'']) => {
+                    Doc::text(format!("{quote}{content}{quote}"))
+                }
+                _ => Doc::text(text.to_string()),
+            }
+        }
+        _ => print_node(node, ctx),
+    }
+}
+/// Only keys of single literal or identifier can be measured.
+fn get_dict_key_width(item: &SyntaxNode) -> Option<usize> {
+    item.first_child()
+        .filter(|key| {
+            matches!(
+                key.kind(),
+                SyntaxKind::EXPR_LITERAL | SyntaxKind::EXPR_IDENT
+            )
+        })
+        .and_then(|key| key.first_token())
+        .map(|token| token.text().chars().count())
+}
 
 fn print_expr_filter(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
     print_expr_with_operator(node, ctx).group()
 }
 
 fn print_expr_get(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
-    print_without_whitespaces(node, ctx)
+    // Number followed by dot will be parsed as float, so it must be wrapped with parens.
+    // This also applies to attribute access ending with number like `a.0`.
+    let ends_with_number = |node: &SyntaxNode| {
+        support::token(node, SyntaxKind::NUMBER).is_some()
+            || node.kind() == SyntaxKind::EXPR_GET_ATTR
+                && node
+                    .last_child()
+                    .is_some_and(|attr| support::token(&attr, SyntaxKind::NUMBER).is_some())
+    };
+    let needs_parens = |child: &SyntaxNode| {
+        // Django parses indexes like `a.0.b` correctly, and doesn't allow parens
+        ctx.options.dialect != Dialect::Django
+            && node.kind() == SyntaxKind::EXPR_GET_ATTR
+            && child.prev_sibling().is_none()
+            && ends_with_number(child)
+    };
+    Doc::list(
+        node.children_with_tokens()
+            .filter(|node_or_token| node_or_token.kind() != SyntaxKind::WHITESPACE)
+            .map(|node_or_token| match node_or_token {
+                NodeOrToken::Node(child) if needs_parens(&child) => Doc::text("(")
+                    .append(print_node(&child, ctx))
+                    .append(Doc::text(")")),
+                NodeOrToken::Node(child) => print_node(&child, ctx),
+                NodeOrToken::Token(token) => Doc::text(token.text().to_string()),
+            })
+            .collect(),
+    )
 }
 
 fn print_expr_ident(node: &SyntaxNode) -> Doc<'static> {
+    // test names of Twig can have multiple words, like `same as`
     node.first_token()
-        .map(|token| Doc::text(token.text().to_string()))
+        .map(|token| {
+            Doc::text(
+                token
+                    .text()
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            )
+        })
         .unwrap_or_else(Doc::nil)
 }
 
@@ -197,6 +428,7 @@ fn print_expr_list(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
         ctx.options.expr_list_trailing_comma,
         ctx.options.expr_list_prefer_single_line,
         ctx.options.bracket_spacing,
+        false,
     )
 }
 
@@ -207,7 +439,38 @@ fn print_expr_literal(node: &SyntaxNode, _: &Ctx) -> Doc<'static> {
 }
 
 fn print_expr_paren(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
-    print_without_whitespaces(node, ctx)
+    if ctx.options.remove_redundant_parens && is_redundant_paren(node, ctx) {
+        node.first_child()
+            .map(|child| print_node(&child, ctx))
+            .unwrap_or_else(Doc::nil)
+    } else {
+        print_without_whitespaces(node, ctx)
+    }
+}
+
+fn print_expr_ternary(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
+    let mut children = node.children();
+    children
+        .next()
+        .map(|condition| print_node(&condition, ctx))
+        .unwrap_or_else(Doc::nil)
+        .append(
+            Doc::list(
+                node.children_with_tokens()
+                    .filter_map(|node_or_token| node_or_token.into_token())
+                    .filter(|token| token.kind() == SyntaxKind::OPERATOR)
+                    .zip(children)
+                    .map(|(operator, branch)| {
+                        Doc::line_or_space()
+                            .append(Doc::text(operator.text().to_string()))
+                            .append(Doc::space())
+                            .append(print_node(&branch, ctx))
+                    })
+                    .collect(),
+            )
+            .nest(ctx.indent_width),
+        )
+        .group()
 }
 
 fn print_expr_test(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
@@ -225,6 +488,7 @@ fn print_expr_tuple(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
         },
         ctx.options.expr_tuple_prefer_single_line,
         ctx.options.tuple_paren_spacing,
+        false,
     )
 }
 
@@ -260,10 +524,7 @@ fn print_expr_with_operator(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
                 }
                 NodeOrToken::Token(token) => {
                     if token.kind() == SyntaxKind::OPERATOR {
-                        let (prefix, suffix) = get_operator_space(ctx);
-                        prefix
-                            .append(Doc::text(token.text().to_string()))
-                            .append(suffix)
+                        print_spaced_operator(&token, ctx)
                     } else {
                         Doc::text(token.text().to_string())
                     }
@@ -274,7 +535,28 @@ fn print_expr_with_operator(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
 }
 
 fn print_param(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
-    print_without_whitespaces(node, ctx)
+    if !ctx.options.params_align_defaults || support::token(node, SyntaxKind::EQ).is_none() {
+        return print_with_kwarg_eq(node, ctx, Doc::nil);
+    }
+    let name_width = |node: &SyntaxNode| {
+        support::token(node, SyntaxKind::IDENT)
+            .map(|token| token.text().chars().count())
+            .unwrap_or_default()
+    };
+    let max_width = node
+        .parent()
+        .into_iter()
+        .flat_map(|parent| parent.children())
+        .filter(|param| {
+            param.kind() == SyntaxKind::PARAM && support::token(param, SyntaxKind::EQ).is_some()
+        })
+        .map(|param| name_width(&param))
+        .max()
+        .unwrap_or_default();
+    let padding = " ".repeat(max_width - name_width(node));
+    print_with_kwarg_eq(node, ctx, || {
+        Doc::flat_or_break(Doc::nil(), Doc::text(padding.clone()))
+    })
 }
 
 fn print_root(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
@@ -303,24 +585,25 @@ fn print_stmt_call(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
             ctx.options.args_trailing_comma,
             ctx.options.args_prefer_single_line,
             ctx.options.args_paren_spacing,
+            false,
         ))
 }
 
 fn print_stmt_filter(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
-    Doc::text("filter ")
-        .append(
-            support::token(node, SyntaxKind::IDENT)
-                .map(|token| Doc::text(token.text().to_string()))
-                .unwrap_or_else(Doc::nil),
-        )
-        .append(print_comma_separated_with_delimiter(
-            node.children_with_tokens()
-                .skip_while(|node_or_token| node_or_token.kind() != SyntaxKind::L_PAREN),
-            ctx,
-            ctx.options.args_trailing_comma,
-            ctx.options.args_prefer_single_line,
-            ctx.options.args_paren_spacing,
-        ))
+    Doc::list(
+        node.children_with_tokens()
+            .filter(|node_or_token| node_or_token.kind() != SyntaxKind::WHITESPACE)
+            .map(|node_or_token| match node_or_token {
+                NodeOrToken::Node(node) => print_node(&node, ctx),
+                NodeOrToken::Token(token) => match token.kind() {
+                    SyntaxKind::KEYWORD => Doc::text("filter "),
+                    SyntaxKind::OPERATOR => print_spaced_operator(&token, ctx),
+                    _ => Doc::text(token.text().to_string()),
+                },
+            })
+            .collect(),
+    )
+    .group()
 }
 
 fn print_stmt_for(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
@@ -331,8 +614,8 @@ fn print_stmt_for(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
                 NodeOrToken::Node(node) => print_node(&node, ctx),
                 NodeOrToken::Token(token) => match token.kind() {
                     SyntaxKind::KEYWORD => match token.text() {
-                        "for" => Doc::text("for "),
-                        "recursive" => Doc::text(" recursive"),
+                        "for" | "asyncEach" | "asyncAll" => Doc::text(format!("{} ", token.text())),
+                        "recursive" | "reversed" => Doc::text(format!(" {}", token.text())),
                         "if" => Doc::line_or_space().append(Doc::text("if ")),
                         text => Doc::text(format!(" {text} ")),
                     },
@@ -359,6 +642,7 @@ fn print_stmt_macro(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
             ctx.options.params_trailing_comma,
             ctx.options.params_prefer_single_line,
             ctx.options.params_paren_spacing,
+            false,
         ))
 }
 
@@ -374,12 +658,7 @@ fn print_stmt_set(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
                         .append(Doc::text(token.text().to_string()))
                         .append(Doc::space()),
                     SyntaxKind::COMMA => Doc::text(token.text().to_string()).append(Doc::space()),
-                    SyntaxKind::OPERATOR => {
-                        let (prefix, suffix) = get_operator_space(ctx);
-                        prefix
-                            .append(Doc::text(token.text().to_string()))
-                            .append(suffix)
-                    }
+                    SyntaxKind::OPERATOR => print_spaced_operator(&token, ctx),
                     _ => Doc::text(token.text().to_string()),
                 },
             })
@@ -407,7 +686,11 @@ fn print_stmt_unknown(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
                     }
                 }
                 NodeOrToken::Token(token) => match token.kind() {
-                    SyntaxKind::KEYWORD | SyntaxKind::COMMA => {
+                    SyntaxKind::KEYWORD | SyntaxKind::COMMA
+                        if token.siblings_with_tokens(Direction::Next).skip(1).any(
+                            |node_or_token| node_or_token.kind() != SyntaxKind::WHITESPACE,
+                        ) =>
+                    {
                         Doc::text(token.text().to_string()).append(Doc::space())
                     }
                     _ => Doc::text(token.text().to_string()),
@@ -448,12 +731,40 @@ fn print_without_whitespaces(node: &SyntaxNode, ctx: &Ctx) -> Doc<'static> {
     )
 }
 
+fn print_with_kwarg_eq(
+    node: &SyntaxNode,
+    ctx: &Ctx,
+    padding: impl Fn() -> Doc<'static>,
+) -> Doc<'static> {
+    Doc::list(
+        node.children_with_tokens()
+            .filter(|node_or_token| node_or_token.kind() != SyntaxKind::WHITESPACE)
+            .map(|node_or_token| match node_or_token {
+                NodeOrToken::Node(node) => print_node(&node, ctx),
+                NodeOrToken::Token(token) if token.kind() == SyntaxKind::EQ => {
+                    // Django doesn't allow spaces around `=` of keyword arguments
+                    if ctx.options.kwarg_eq_spacing && ctx.options.dialect != Dialect::Django {
+                        padding()
+                            .append(Doc::space())
+                            .append(Doc::text("="))
+                            .append(Doc::space())
+                    } else {
+                        padding().append(Doc::text("="))
+                    }
+                }
+                NodeOrToken::Token(token) => Doc::text(token.text().to_string()),
+            })
+            .collect(),
+    )
+}
+
 fn print_comma_separated_with_delimiter(
     elements: impl Iterator<Item = NodeOrToken>,
     ctx: &Ctx,
     trailing_comma: Option<TrailingComma>,
     prefer_single_line: Option<bool>,
     delim_spacing: bool,
+    always_break: bool,
 ) -> Doc<'static> {
     Doc::list(
         elements
@@ -482,12 +793,12 @@ fn print_comma_separated_with_delimiter(
                         let doc = Doc::text(token.text().to_string());
                         match token.kind() {
                             SyntaxKind::L_PAREN | SyntaxKind::L_BRACKET | SyntaxKind::L_BRACE => {
-                                let ws = if !prefer_single_line
-                                    .unwrap_or(ctx.options.prefer_single_line)
-                                    && token.next_token().is_some_and(|token| {
-                                        token.kind() == SyntaxKind::WHITESPACE
-                                            && token.text().contains('\n')
-                                    }) {
+                                let ws = if always_break
+                                    || !prefer_single_line.unwrap_or(ctx.options.prefer_single_line)
+                                        && token.next_token().is_some_and(|token| {
+                                            token.kind() == SyntaxKind::WHITESPACE
+                                                && token.text().contains('\n')
+                                        }) {
                                     Doc::hard_line()
                                 } else if delim_spacing {
                                     Doc::line_or_space()
@@ -514,6 +825,27 @@ fn print_comma_separated_with_delimiter(
     .group()
 }
 
+/// Operators of multiple words like `not in` are printed with single space between words.
+fn print_operator(token: &SyntaxToken) -> Doc<'static> {
+    Doc::text(
+        token
+            .text()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+/// Print operator with spaces or line break around it.
+/// Pipes of filters in Django are conventionally printed without spaces, like `a|lower`.
+fn print_spaced_operator(token: &SyntaxToken, ctx: &Ctx) -> Doc<'static> {
+    if ctx.options.dialect == Dialect::Django && token.text() == "|" {
+        return Doc::text("|");
+    }
+    let (prefix, suffix) = get_operator_space(ctx);
+    prefix.append(print_operator(token)).append(suffix)
+}
+
 fn get_operator_space(ctx: &Ctx) -> (Doc<'static>, Doc<'static>) {
     use crate::config::OperatorLineBreak;
     match ctx.options.operator_linebreak {
@@ -521,3 +853,175 @@ fn get_operator_space(ctx: &Ctx) -> (Doc<'static>, Doc<'static>) {
         OperatorLineBreak::After => (Doc::space(), Doc::line_or_space().nest(ctx.indent_width)),
     }
 }
+
+/// Precedence levels of expressions, from the loosest to the tightest.
+///
+/// The order follows Jinja's own parser rather than ours,
+/// so parentheses are only considered redundant when both parsers agree.
+/// Operators which only exist in other dialects follow parsers of those template engines.
+mod precedence {
+    pub(super) const IF: u8 = 0;
+    pub(super) const TERNARY: u8 = 1;
+    pub(super) const COALESCE: u8 = 2;
+    pub(super) const OR: u8 = 3;
+    pub(super) const AND: u8 = 4;
+    pub(super) const BIT_OR: u8 = 5;
+    pub(super) const BIT_XOR: u8 = 6;
+    pub(super) const BIT_AND: u8 = 7;
+    pub(super) const NOT: u8 = 8;
+    pub(super) const CMP: u8 = 9;
+    pub(super) const RANGE: u8 = 10;
+    pub(super) const ADD: u8 = 11;
+    pub(super) const CONCAT: u8 = 12;
+    pub(super) const MUL: u8 = 13;
+    pub(super) const SIGNED: u8 = 14;
+    pub(super) const POW: u8 = 15;
+    pub(super) const FILTER: u8 = 16;
+    pub(super) const ATOM: u8 = 17;
+}
+
+fn get_bin_operator_precedence(node: &SyntaxNode) -> u8 {
+    match support::token(node, SyntaxKind::OPERATOR)
+        .as_ref()
+        .map(|token| token.text())
+    {
+        Some("??" | "?:") => precedence::COALESCE,
+        Some("or") => precedence::OR,
+        Some("and") => precedence::AND,
+        Some("b-or") => precedence::BIT_OR,
+        Some("b-xor") => precedence::BIT_XOR,
+        Some("b-and") => precedence::BIT_AND,
+        Some("..") => precedence::RANGE,
+        Some("+" | "-") => precedence::ADD,
+        Some("*" | "/" | "//" | "%") => precedence::MUL,
+        Some("**") => precedence::POW,
+        _ => precedence::CMP,
+    }
+}
+
+fn get_expr_precedence(node: &SyntaxNode) -> u8 {
+    match node.kind() {
+        SyntaxKind::EXPR_IF | SyntaxKind::EXPR_ARROW => precedence::IF,
+        SyntaxKind::EXPR_TERNARY => precedence::TERNARY,
+        SyntaxKind::EXPR_BIN => get_bin_operator_precedence(node),
+        SyntaxKind::EXPR_UNARY => precedence::NOT,
+        SyntaxKind::EXPR_CONCAT => precedence::CONCAT,
+        SyntaxKind::EXPR_TEST | SyntaxKind::EXPR_FILTER => precedence::FILTER,
+        SyntaxKind::EXPR_LITERAL
+            if support::token(node, SyntaxKind::NUMBER)
+                .is_some_and(|token| token.text().starts_with(['+', '-'])) =>
+        {
+            precedence::SIGNED
+        }
+        _ => precedence::ATOM,
+    }
+}
+
+/// Get the expression which will be printed actually if parens are redundant and will be removed.
+fn skip_redundant_parens(mut node: SyntaxNode, ctx: &Ctx) -> SyntaxNode {
+    while node.kind() == SyntaxKind::EXPR_PAREN
+        && ctx.options.remove_redundant_parens
+        && is_redundant_paren(&node, ctx)
+        && let Some(child) = node.first_child()
+    {
+        node = child;
+    }
+    node
+}
+
+fn is_redundant_paren(node: &SyntaxNode, ctx: &Ctx) -> bool {
+    let Some(parent) = node.parent() else {
+        return false;
+    };
+    let mut inner = node.first_child();
+    while let Some(child) = inner
+        .as_ref()
+        .filter(|inner| inner.kind() == SyntaxKind::EXPR_PAREN)
+    {
+        inner = child.first_child();
+    }
+    let Some(inner) = inner else {
+        return false;
+    };
+    let inner_precedence = get_expr_precedence(&inner);
+    let is_first = node.prev_sibling().is_none();
+    match parent.kind() {
+        SyntaxKind::EXPR_PAREN => true,
+        SyntaxKind::EXPR_BIN => {
+            let parent_precedence = get_bin_operator_precedence(&parent);
+            // precedence of `??` differs between versions of Twig
+            if parent_precedence == precedence::COALESCE {
+                return inner_precedence == precedence::ATOM;
+            }
+            if ctx.clarify_mixed_and_or()
+                && parent_precedence == precedence::OR
+                && inner.kind() == SyntaxKind::EXPR_BIN
+                && get_bin_operator_precedence(&inner) == precedence::AND
+            {
+                return false;
+            }
+            // Binary operators are parsed as left-associative, so parens of right operand
+            // with the same precedence can't be removed without regrouping operands.
+            // Comparisons are chained and `**` is right-associative, so they can't be regrouped.
+            let is_associative = is_first
+                && !matches!(
+                    parent_precedence,
+                    precedence::CMP | precedence::RANGE | precedence::POW
+                );
+            if is_associative {
+                inner_precedence >= parent_precedence
+            } else {
+                inner_precedence > parent_precedence
+            }
+        }
+        SyntaxKind::EXPR_IF => inner_precedence > precedence::IF,
+        SyntaxKind::EXPR_TERNARY => inner_precedence > precedence::TERNARY,
+        SyntaxKind::EXPR_UNARY => matches!(
+            inner_precedence,
+            precedence::CONCAT | precedence::SIGNED | precedence::FILTER | precedence::ATOM
+        ),
+        // operands of `~` are parsed as a flat list, so nested `~` can't be flattened
+        SyntaxKind::EXPR_CONCAT => matches!(
+            inner_precedence,
+            precedence::SIGNED | precedence::FILTER | precedence::ATOM
+        ),
+        SyntaxKind::EXPR_TEST if is_first => {
+            inner.kind() == SyntaxKind::EXPR_FILTER || inner_precedence == precedence::ATOM
+        }
+        SyntaxKind::EXPR_GET_ATTR
+            if is_first && support::token(&inner, SyntaxKind::NUMBER).is_some() =>
+        {
+            false
+        }
+        SyntaxKind::EXPR_TEST
+        | SyntaxKind::EXPR_FILTER
+        | SyntaxKind::EXPR_GET_ATTR
+        | SyntaxKind::EXPR_GET_ITEM
+        | SyntaxKind::EXPR_CALL
+            if is_first =>
+        {
+            inner_precedence == precedence::ATOM
+        }
+        SyntaxKind::EXPR_TEST | SyntaxKind::EXPR_GET_ATTR => inner_precedence == precedence::ATOM,
+        SyntaxKind::ARG
+            if parent
+                .parent()
+                .is_some_and(|call| support::token(&call, SyntaxKind::L_PAREN).is_none()) =>
+        {
+            inner_precedence == precedence::ATOM
+        }
+        // Jinja doesn't allow conditional expression as iterable of `for` loop.
+        SyntaxKind::STMT_FOR => inner_precedence > precedence::IF,
+        SyntaxKind::STMT_UNKNOWN => parent.children().nth(1).is_none(),
+        _ => true,
+    }
+}
+
+fn is_and_in_or(node: &SyntaxNode) -> bool {
+    node.kind() == SyntaxKind::EXPR_BIN
+        && get_bin_operator_precedence(node) == precedence::AND
+        && node.parent().is_some_and(|parent| {
+            parent.kind() == SyntaxKind::EXPR_BIN
+                && get_bin_operator_precedence(&parent) == precedence::OR
+        })
+}
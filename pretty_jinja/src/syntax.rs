@@ -1,5 +1,7 @@
+//! Syntax tree types returned by [`parse_expr`](crate::parse_expr) and [`parse_stmt`](crate::parse_stmt).
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub(crate) enum JinjaLanguage {}
+pub enum JinjaLanguage {}
 impl rowan::Language for JinjaLanguage {
     type Kind = SyntaxKind;
     fn kind_from_raw(raw: rowan::SyntaxKind) -> Self::Kind {
@@ -15,13 +17,15 @@ impl rowan::Language for JinjaLanguage {
 #[allow(non_camel_case_types)]
 #[allow(clippy::upper_case_acronyms)]
 #[repr(u16)]
-pub(crate) enum SyntaxKind {
+pub enum SyntaxKind {
     // SyntaxToken
     WHITESPACE = 0,
+    BOM,
     BOOL,
     IDENT,
     NUMBER,
     STRING,
+    REGEX,
     L_PAREN,
     R_PAREN,
     L_BRACKET,
@@ -29,6 +33,7 @@ pub(crate) enum SyntaxKind {
     L_BRACE,
     R_BRACE,
     COLON,
+    DOUBLE_COLON,
     COMMA,
     DOT,
     EQ,
@@ -38,6 +43,7 @@ pub(crate) enum SyntaxKind {
     // SyntaxNode
     ARG,
     CALL_PARAMS,
+    EXPR_ARROW,
     EXPR_BIN,
     EXPR_CALL,
     EXPR_CONCAT,
@@ -51,6 +57,8 @@ pub(crate) enum SyntaxKind {
     EXPR_LIST,
     EXPR_LITERAL,
     EXPR_PAREN,
+    EXPR_PATH,
+    EXPR_TERNARY,
     EXPR_TEST,
     EXPR_TUPLE,
     EXPR_UNARY,
@@ -72,6 +80,6 @@ impl From<SyntaxKind> for rowan::SyntaxKind {
     }
 }
 
-pub(crate) type SyntaxNode = rowan::SyntaxNode<JinjaLanguage>;
-pub(crate) type SyntaxToken = rowan::SyntaxToken<JinjaLanguage>;
+pub type SyntaxNode = rowan::SyntaxNode<JinjaLanguage>;
+pub type SyntaxToken = rowan::SyntaxToken<JinjaLanguage>;
 pub(crate) type NodeOrToken = rowan::NodeOrToken<SyntaxNode, SyntaxToken>;
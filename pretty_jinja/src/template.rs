@@ -0,0 +1,418 @@
+use crate::{
+    config::{Dialect, FormatOptions},
+    edit, parser,
+};
+use rowan::TextRange;
+use std::ops::Range;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TagKind {
+    Expr,
+    Stmt,
+    Comment,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Tag {
+    pub kind: TagKind,
+    /// Range of the whole tag, including delimiters.
+    pub range: Range<usize>,
+    /// Range of tag content, excluding delimiters and whitespace control markers.
+    pub inner: Range<usize>,
+}
+
+/// Error of unclosed tag or block found when scanning template.
+#[derive(Clone, Debug)]
+pub(crate) struct ScanError {
+    /// Start of the unclosed tag.
+    pub offset: usize,
+    pub message: &'static str,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl ScanError {
+    fn new(code: &str, offset: usize, message: &'static str) -> Self {
+        let (line, column) = get_line_col(code, offset);
+        Self {
+            offset,
+            message,
+            line,
+            column,
+        }
+    }
+}
+
+impl From<ScanError> for String {
+    fn from(error: ScanError) -> Self {
+        format!(
+            "{} at line {}, column {}",
+            error.message, error.line, error.column
+        )
+    }
+}
+
+/// Find all Jinja tags in template.
+/// Content of `{% raw %}` blocks, or `{% verbatim %}` blocks of Twig and Django, is skipped.
+pub(crate) fn scan_tags(code: &str, dialect: Dialect) -> Result<Vec<Tag>, ScanError> {
+    let markers = get_whitespace_markers(dialect);
+    let quotes = get_quotes(dialect);
+    let bytes = code.as_bytes();
+    let mut tags = Vec::new();
+    let mut pos = 0;
+    while let Some(offset) = code[pos..].find('{') {
+        let start = pos + offset;
+        let kind = match bytes.get(start + 1) {
+            Some(b'{') => TagKind::Expr,
+            Some(b'%') => TagKind::Stmt,
+            Some(b'#') => TagKind::Comment,
+            _ => {
+                pos = start + 1;
+                continue;
+            }
+        };
+        let mut inner_start = start + 2;
+        if bytes
+            .get(inner_start)
+            .is_some_and(|byte| markers.contains(&char::from(*byte)))
+            && kind != TagKind::Comment
+        {
+            inner_start += 1;
+        }
+        let (inner_end, end) = match kind {
+            TagKind::Comment => code[inner_start..]
+                .find("#}")
+                .map(|offset| (inner_start + offset, inner_start + offset + 2)),
+            TagKind::Expr => find_tag_end(code, inner_start, b'}', markers, quotes),
+            TagKind::Stmt => find_tag_end(code, inner_start, b'%', markers, quotes),
+        }
+        .ok_or_else(|| ScanError::new(code, start, "unclosed tag"))?;
+        tags.push(Tag {
+            kind,
+            range: start..end,
+            inner: inner_start..inner_end,
+        });
+        pos = end;
+
+        if kind == TagKind::Stmt
+            && let Some((name, args)) = get_raw_tag(&code[inner_start..inner_end], dialect)
+        {
+            match find_endraw(code, end, name, args, markers) {
+                Some(endraw) => pos = endraw,
+                None => return Err(ScanError::new(code, start, "unclosed raw block")),
+            }
+        }
+    }
+    Ok(tags)
+}
+
+/// Find end of expression or statement tag,
+/// skipping closing delimiters in strings or nested brackets.
+fn find_tag_end(
+    code: &str,
+    start: usize,
+    delim: u8,
+    markers: &[char],
+    quotes: &[u8],
+) -> Option<(usize, usize)> {
+    let bytes = code.as_bytes();
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut i = start;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        if let Some(q) = quote {
+            if byte == b'\\' {
+                i += 1;
+            } else if byte == q {
+                quote = None;
+            }
+        } else {
+            match byte {
+                _ if quotes.contains(&byte) => quote = Some(byte),
+                _ if markers.contains(&char::from(byte))
+                    && depth == 0
+                    && bytes.get(i + 1..i + 3) == Some(&[delim, b'}']) =>
+                {
+                    return Some((i, i + 3));
+                }
+                _ if byte == delim && depth == 0 && bytes.get(i + 1) == Some(&b'}') => {
+                    return Some((i, i + 2));
+                }
+                b'(' | b'[' | b'{' => depth += 1,
+                b')' | b']' | b'}' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Quotes of string literals. Tera has strings quoted with backticks.
+fn get_quotes(dialect: Dialect) -> &'static [u8] {
+    match dialect {
+        Dialect::Tera => b"'\"`",
+        _ => b"'\"",
+    }
+}
+
+/// Characters after opening delimiter or before closing delimiter that control whitespaces,
+/// such as `-` in `{%- if %}`.
+fn get_whitespace_markers(dialect: Dialect) -> &'static [char] {
+    match dialect {
+        Dialect::Twig => &['-', '~'],
+        Dialect::Django => &[],
+        _ => &['-', '+'],
+    }
+}
+
+/// Name of tag whose content is kept as-is, such as `raw` of Jinja or `verbatim` of Twig,
+/// and arguments that its end tag must have.
+fn get_raw_tag(inner: &str, dialect: Dialect) -> Option<(&'static str, &str)> {
+    let inner = inner.trim();
+    match dialect {
+        Dialect::Twig => (inner == "verbatim").then_some(("verbatim", "")),
+        Dialect::Django => {
+            let (name, args) = inner.split_once(char::is_whitespace).unwrap_or((inner, ""));
+            match name {
+                // `{% verbatim myblock %}` is closed by `{% endverbatim myblock %}`
+                "verbatim" => Some(("verbatim", args)),
+                // `{% comment %}` can have a note like `{% comment "TODO" %}`
+                "comment" => Some(("comment", "")),
+                _ => None,
+            }
+        }
+        _ => (inner == "raw").then_some(("raw", "")),
+    }
+}
+
+/// Find the start of end tag of raw block like `{% endraw %}`, so it will be scanned as a normal tag.
+fn find_endraw(
+    code: &str,
+    start: usize,
+    name: &str,
+    args: &str,
+    markers: &[char],
+) -> Option<usize> {
+    let mut pos = start;
+    while let Some(offset) = code[pos..].find("{%") {
+        let tag_start = pos + offset;
+        let rest = code[tag_start + 2..].trim_start_matches(markers);
+        if let Some(rest) = rest
+            .trim_start()
+            .strip_prefix("end")
+            .and_then(|rest| rest.strip_prefix(name))
+            && let Some((inner, _)) = rest.split_once("%}")
+            && let inner = inner.trim_end_matches(markers)
+            && (inner.is_empty() || inner.starts_with(char::is_whitespace))
+            && inner.split_whitespace().eq(args.split_whitespace())
+        {
+            return Some(tag_start);
+        }
+        pos = tag_start + 2;
+    }
+    None
+}
+
+pub(crate) fn get_line_col(code: &str, offset: usize) -> (usize, usize) {
+    let before = &code[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rfind('\n').map_or(offset, |i| offset - i - 1) + 1;
+    (line, column)
+}
+
+/// Format selected tags in order, and call `f` with each formatted tag.
+/// Other tags and tags that can't be parsed are kept as-is. It returns the whole formatted template.
+///
+/// Indentation and column of each tag are computed from formatted text before it,
+/// so previous tags on the same line are taken into account.
+pub(crate) fn format_tags(
+    code: &str,
+    tags: &[Tag],
+    options: &FormatOptions,
+    select: impl Fn(&Tag) -> bool,
+    mut f: impl FnMut(&Tag, &str),
+) -> String {
+    let mut output = String::with_capacity(code.len());
+    let mut pos = 0;
+    for tag in tags {
+        output.push_str(&code[pos..tag.range.start]);
+        let line_prefix = &output[output.rfind('\n').map_or(0, |i| i + 1)..];
+        if select(tag)
+            && let Some(formatted) = format_tag(code, tag, line_prefix, options)
+        {
+            f(tag, &formatted);
+            output.push_str(&formatted);
+        } else {
+            output.push_str(&code[tag.range.clone()]);
+        }
+        pos = tag.range.end;
+    }
+    output.push_str(&code[pos..]);
+    output
+}
+
+/// Format a single tag and return its new text including delimiters,
+/// or `None` if it can't be parsed, such as tags not supported by the dialect.
+///
+/// `line_prefix` is the text from the start of line to the tag.
+fn format_tag(code: &str, tag: &Tag, line_prefix: &str, options: &FormatOptions) -> Option<String> {
+    let open = &code[tag.range.start..tag.inner.start];
+    let close = &code[tag.inner.end..tag.range.end];
+    let content = &code[tag.inner.clone()];
+    let format = match tag.kind {
+        TagKind::Expr => crate::format_expr,
+        TagKind::Stmt => crate::format_stmt,
+        TagKind::Comment => return Some(code[tag.range.clone()].to_string()),
+    };
+
+    let indent =
+        &line_prefix[..line_prefix.len() - line_prefix.trim_start_matches([' ', '\t']).len()];
+    let column = line_prefix.chars().count();
+    let mut options = options.clone();
+    options.layout.print_width = options
+        .layout
+        .print_width
+        .saturating_sub(column + open.len() + close.len() + 2);
+
+    let formatted = format(content, &options).ok()?;
+    let formatted = if indent.is_empty() {
+        formatted
+    } else {
+        formatted.replace('\n', &format!("\n{indent}"))
+    };
+    Some(format!("{open} {formatted} {close}"))
+}
+
+/// Compute text edits against the whole template from formatted tag.
+pub(crate) fn tag_edits(
+    code: &str,
+    tag: &Tag,
+    formatted: &str,
+    dialect: Dialect,
+) -> Vec<(TextRange, String)> {
+    let old = &code[tag.range.clone()];
+    if formatted == old {
+        return Vec::new();
+    }
+    let parse = match tag.kind {
+        TagKind::Expr => parser::parse_expr,
+        TagKind::Stmt => parser::parse_stmt,
+        TagKind::Comment => return Vec::new(),
+    };
+    let open_len = tag.inner.start - tag.range.start;
+    let close_len = tag.range.end - tag.inner.end;
+
+    let mut old_tokens = Vec::new();
+    old_tokens.push(0..open_len);
+    if let Ok(node) = parse(&code[tag.inner.clone()], dialect) {
+        edit::collect_tokens(&node, open_len, &mut old_tokens);
+    }
+    old_tokens.push(old.len() - close_len..old.len());
+
+    let mut new_tokens = Vec::new();
+    new_tokens.push(0..open_len);
+    if let Ok(node) = parse(&formatted[open_len..formatted.len() - close_len], dialect) {
+        edit::collect_tokens(&node, open_len, &mut new_tokens);
+    }
+    new_tokens.push(formatted.len() - close_len..formatted.len());
+
+    let mut edits = edit::diff(old, &old_tokens, formatted, &new_tokens);
+    edit::shift(&mut edits, tag.range.start);
+    edits
+}
+
+/// Check syntax of a single tag, and return range and message of syntax error if any.
+pub(crate) fn check_tag(code: &str, tag: &Tag, dialect: Dialect) -> Option<(Range<usize>, String)> {
+    let content = &code[tag.inner.clone()];
+    let offset = match tag.kind {
+        TagKind::Expr => parser::parse_expr(content, dialect).err()?.offset(),
+        TagKind::Stmt => parser::parse_stmt(content, dialect).err()?.offset(),
+        TagKind::Comment => return None,
+    };
+    let rest = code[tag.inner.start + offset..tag.inner.end].trim_start();
+    let start = tag.inner.end - rest.len();
+    let token_len = match rest.chars().next() {
+        Some(c) if c.is_alphanumeric() || c == '_' => rest
+            .find(|c: char| !c.is_alphanumeric() && c != '_')
+            .unwrap_or(rest.len()),
+        Some(c) => c.len_utf8(),
+        None => 0,
+    };
+    let (end, message) = if token_len == 0 {
+        (tag.range.end, "unexpected end of tag".into())
+    } else {
+        (
+            start + token_len,
+            format!("unexpected `{}`", &rest[..token_len]),
+        )
+    };
+    let start = if token_len == 0 { tag.inner.end } else { start };
+    Some((start..end, message))
+}
+
+/// Check that formatted template has the same text outside tags and tags have the same meaning.
+pub(crate) fn verify(code: &str, formatted: &str, dialect: Dialect) -> Result<(), String> {
+    let tags = scan_tags(code, dialect)?;
+    let formatted_tags = scan_tags(formatted, dialect).map_err(|error| {
+        format!(
+            "formatted template can't be parsed: {}",
+            String::from(error)
+        )
+    })?;
+    if tags.len() != formatted_tags.len() {
+        return Err(format!(
+            "formatter changed number of tags from {} to {}",
+            tags.len(),
+            formatted_tags.len()
+        ));
+    }
+    let mut pos = 0;
+    let mut formatted_pos = 0;
+    for (tag, formatted_tag) in tags.iter().zip(&formatted_tags) {
+        let (line, column) = get_line_col(code, tag.range.start);
+        if code[pos..tag.range.start] != formatted[formatted_pos..formatted_tag.range.start]
+            || code[tag.range.start..tag.inner.start]
+                != formatted[formatted_tag.range.start..formatted_tag.inner.start]
+            || code[tag.inner.end..tag.range.end]
+                != formatted[formatted_tag.inner.end..formatted_tag.range.end]
+        {
+            return Err(format!(
+                "formatter changed text outside tag at line {line}, column {column}"
+            ));
+        }
+        pos = tag.range.end;
+        formatted_pos = formatted_tag.range.end;
+
+        let content = &code[tag.inner.clone()];
+        let formatted_content = &formatted[formatted_tag.inner.clone()];
+        let parse = match tag.kind {
+            TagKind::Expr => parser::parse_expr,
+            TagKind::Stmt => parser::parse_stmt,
+            TagKind::Comment if content == formatted_content => continue,
+            TagKind::Comment => {
+                return Err(format!(
+                    "formatter changed comment at line {line}, column {column}"
+                ));
+            }
+        };
+        // tags that can't be parsed must be kept as-is
+        let Ok(node) = parse(content, dialect) else {
+            if content == formatted_content {
+                continue;
+            }
+            return Err(format!(
+                "formatter changed tag that can't be parsed at line {line}, column {column}"
+            ));
+        };
+        let formatted_node = parse(formatted_content, dialect).map_err(|error| {
+            format!("formatted tag at line {line}, column {column} can't be parsed: {error}")
+        })?;
+        crate::verify::compare(&node, &formatted_node, tag.inner.start, dialect)?;
+    }
+    if code[pos..] != formatted[formatted_pos..] {
+        return Err("formatter changed text after the last tag".into());
+    }
+    Ok(())
+}
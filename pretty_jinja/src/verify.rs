@@ -0,0 +1,176 @@
+//! Checking that formatted code is stable and has the same meaning as the original code.
+
+use crate::{
+    config::Dialect,
+    syntax::{NodeOrToken, SyntaxKind, SyntaxNode},
+};
+use rowan::WalkEvent;
+use std::fmt;
+
+/// Element of syntax tree that affects meaning of code.
+///
+/// Parens of parenthesized expressions and calls are ignored since their meaning is
+/// already represented by tree structure, so formatter can add or remove redundant parens
+/// and add parens to single-argument calls of tests like `is divisibleby 3`.
+#[derive(Debug, PartialEq, Eq)]
+enum Element {
+    Enter(SyntaxKind),
+    Leave(SyntaxKind),
+    Token(SyntaxKind, String),
+}
+
+impl fmt::Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Element::Enter(kind) => write!(f, "start of {kind:?}"),
+            Element::Leave(kind) => write!(f, "end of {kind:?}"),
+            Element::Token(_, text) => write!(f, "`{text}`"),
+        }
+    }
+}
+
+/// Collect elements with their offsets, ignoring whitespaces, parens of parenthesized expressions
+/// and trailing commas. Strings are compared by their values, regardless of quotes,
+/// except interpolated strings of Twig,
+/// and operators and test names of multiple words like `starts with` and `same as`
+/// are compared regardless of whitespaces.
+fn collect_elements(node: &SyntaxNode, dialect: Dialect) -> Vec<(usize, Element)> {
+    let mut elements = Vec::new();
+    for event in node.preorder_with_tokens() {
+        match event {
+            WalkEvent::Enter(NodeOrToken::Node(node)) if node.kind() != SyntaxKind::EXPR_PAREN => {
+                elements.push((
+                    node.text_range().start().into(),
+                    Element::Enter(node.kind()),
+                ));
+            }
+            WalkEvent::Leave(NodeOrToken::Node(node)) if node.kind() != SyntaxKind::EXPR_PAREN => {
+                elements.push((node.text_range().end().into(), Element::Leave(node.kind())));
+            }
+            WalkEvent::Enter(NodeOrToken::Token(token)) => {
+                let kind = token.kind();
+                let is_paren = matches!(kind, SyntaxKind::L_PAREN | SyntaxKind::R_PAREN)
+                    && token.parent().is_some_and(|parent| {
+                        matches!(
+                            parent.kind(),
+                            SyntaxKind::EXPR_PAREN | SyntaxKind::EXPR_CALL
+                        )
+                    });
+                let is_trailing_comma = kind == SyntaxKind::COMMA
+                    && token
+                        .siblings_with_tokens(rowan::Direction::Next)
+                        .skip(1)
+                        .find(|element| element.kind() != SyntaxKind::WHITESPACE)
+                        .is_none_or(|element| {
+                            matches!(
+                                element.kind(),
+                                SyntaxKind::R_PAREN | SyntaxKind::R_BRACKET | SyntaxKind::R_BRACE
+                            )
+                        })
+                    // trailing comma of single-element tuple can't be omitted
+                    && token.parent().is_none_or(|parent| {
+                        parent.kind() != SyntaxKind::EXPR_TUPLE
+                            || parent.children().nth(1).is_some()
+                    });
+                if matches!(kind, SyntaxKind::WHITESPACE | SyntaxKind::BOM)
+                    || is_paren
+                    || is_trailing_comma
+                {
+                    continue;
+                }
+                let text = match kind {
+                    // double-quoted strings of Twig are interpolated, like `"#{name}"`
+                    SyntaxKind::STRING
+                        if dialect == Dialect::Twig && token.text().contains("#{") =>
+                    {
+                        token.text().to_string()
+                    }
+                    SyntaxKind::STRING => normalize_string(token.text()),
+                    SyntaxKind::OPERATOR | SyntaxKind::IDENT => token
+                        .text()
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    _ => token.text().to_string(),
+                };
+                elements.push((
+                    token.text_range().start().into(),
+                    Element::Token(kind, text),
+                ));
+            }
+            _ => {}
+        }
+    }
+    elements
+}
+
+/// Get value of string literal, so strings with different quotes can be compared.
+/// Escaped quotes are unescaped, and other escape sequences are kept as-is.
+fn normalize_string(text: &str) -> String {
+    let content = text.get(1..text.len().saturating_sub(1)).unwrap_or(text);
+    let mut value = String::with_capacity(content.len() + 2);
+    value.push('"');
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some(quote @ ('\'' | '"')) => value.push(quote),
+                Some(c) => {
+                    value.push('\\');
+                    value.push(c);
+                }
+                None => value.push('\\'),
+            }
+        } else {
+            value.push(c);
+        }
+    }
+    value.push('"');
+    value
+}
+
+/// Check that both syntax trees have the same meaning.
+///
+/// `offset` is added to offsets of the original code in error message.
+pub(crate) fn compare(
+    original: &SyntaxNode,
+    formatted: &SyntaxNode,
+    offset: usize,
+    dialect: Dialect,
+) -> Result<(), String> {
+    let original = collect_elements(original, dialect);
+    let formatted = collect_elements(formatted, dialect);
+    let mismatch = original
+        .iter()
+        .map(Some)
+        .chain(std::iter::repeat(None))
+        .zip(formatted.iter().map(Some).chain(std::iter::repeat(None)))
+        .take(original.len().max(formatted.len()))
+        .find(|(original, formatted)| original.map(|(_, e)| e) != formatted.map(|(_, e)| e));
+    match mismatch {
+        None => Ok(()),
+        Some((Some((pos, expected)), Some((_, found)))) => Err(format!(
+            "formatter changed meaning of code at offset {}: expected {expected}, found {found}",
+            pos + offset
+        )),
+        Some((Some((pos, expected)), None)) => Err(format!(
+            "formatter changed meaning of code at offset {}: {expected} is missing",
+            pos + offset
+        )),
+        Some((None, Some((_, found)))) => Err(format!(
+            "formatter changed meaning of code: unexpected {found} at the end"
+        )),
+        Some((None, None)) => unreachable!(),
+    }
+}
+
+/// Check that formatting formatted code again doesn't change it.
+pub(crate) fn check_stable(formatted: &str, reformatted: &str) -> Result<(), String> {
+    if formatted == reformatted {
+        Ok(())
+    } else {
+        Err(format!(
+            "format is unstable, formatting\n{formatted}\ngives\n{reformatted}"
+        ))
+    }
+}
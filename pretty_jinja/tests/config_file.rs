@@ -0,0 +1,136 @@
+#![cfg(feature = "config_file")]
+
+use pretty_jinja::{
+    config::TrailingComma,
+    config_file::{ConfigFile, resolve_options},
+};
+use std::{fs, path::PathBuf};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("pretty_jinja_config_file_{name}"));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn pyproject() {
+    let dir = temp_dir("pyproject");
+    fs::create_dir_all(dir.join("templates/emails")).unwrap();
+    fs::write(
+        dir.join("pyproject.toml"),
+        r#"
+[project]
+name = "app"
+
+[tool.pretty_jinja]
+print_width = 100
+trailingComma = "never"
+
+[[tool.pretty_jinja.overrides]]
+files = "emails/*.j2"
+options = { print_width = 120 }
+"#,
+    )
+    .unwrap();
+
+    let options = resolve_options(&dir.join("templates/index.html.j2")).unwrap();
+    assert_eq!(options.layout.print_width, 100);
+    assert!(matches!(
+        options.language.trailing_comma,
+        TrailingComma::Never
+    ));
+
+    let options = resolve_options(&dir.join("templates/emails/welcome.j2")).unwrap();
+    assert_eq!(options.layout.print_width, 100);
+
+    let options = resolve_options(&dir.join("emails/welcome.j2")).unwrap();
+    assert_eq!(options.layout.print_width, 120);
+}
+
+#[test]
+fn pyproject_without_table() {
+    let dir = temp_dir("pyproject_without_table");
+    fs::create_dir_all(dir.join("app")).unwrap();
+    fs::write(
+        dir.join("app/pyproject.toml"),
+        "[project]\nname = \"app\"\n",
+    )
+    .unwrap();
+    fs::write(dir.join(".prettyjinjarc"), r#"{ "indentWidth": 4 }"#).unwrap();
+
+    let config = ConfigFile::find(&dir.join("app/index.j2"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(config.path, dir.join(".prettyjinjarc"));
+    let options = config
+        .format_options_for(&dir.join("app/index.j2"))
+        .unwrap();
+    assert_eq!(options.layout.indent_width, 4);
+}
+
+#[test]
+fn nearest_config_file_wins() {
+    let dir = temp_dir("nearest");
+    fs::create_dir_all(dir.join("a/b")).unwrap();
+    fs::write(dir.join(".pretty_jinja.toml"), "use_tabs = true\n").unwrap();
+    fs::write(
+        dir.join("a/.pretty_jinja.json"),
+        r#"{ "print_width": 60, "overrides": [{ "files": ["*.sql.j2"], "options": { "print_width": 40 } }] }"#,
+    )
+    .unwrap();
+
+    let options = resolve_options(&dir.join("a/b/query.sql.j2")).unwrap();
+    assert!(!options.layout.use_tabs);
+    assert_eq!(options.layout.print_width, 40);
+
+    let options = resolve_options(&dir.join("a/b/index.j2")).unwrap();
+    assert_eq!(options.layout.print_width, 60);
+
+    let options = resolve_options(&dir.join("index.j2")).unwrap();
+    assert!(options.layout.use_tabs);
+}
+
+#[test]
+fn invalid_config_file() {
+    let dir = temp_dir("invalid");
+    fs::write(dir.join(".pretty_jinja.toml"), "print_width = \"wide\"\n").unwrap();
+
+    let error = resolve_options(&dir.join("index.j2")).unwrap_err();
+    assert!(error.starts_with("invalid config file"), "{error}");
+}
+
+#[test]
+fn mixed_key_spellings() {
+    let dir = temp_dir("mixed_key_spellings");
+    fs::write(
+        dir.join(".pretty_jinja.toml"),
+        r#"
+printWidth = 100
+"args.trailingComma" = "never"
+
+[[overrides]]
+files = "*.sql.j2"
+options = { print_width = 120, "args.trailing_comma" = "always" }
+"#,
+    )
+    .unwrap();
+
+    let options = resolve_options(&dir.join("query.sql.j2")).unwrap();
+    assert_eq!(options.layout.print_width, 120);
+    assert!(matches!(
+        options.language.args_trailing_comma,
+        Some(TrailingComma::Always)
+    ));
+
+    let options = resolve_options(&dir.join("index.j2")).unwrap();
+    assert!(matches!(
+        options.language.args_trailing_comma,
+        Some(TrailingComma::Never)
+    ));
+
+    let config = ConfigFile::find(&dir.join("index.j2")).unwrap().unwrap();
+    let options = config.options_for(&dir.join("index.j2"));
+    assert_eq!(options.get("print_width"), Some(&100.into()));
+    assert!(options.get("printWidth").is_none());
+}
@@ -1,6 +1,12 @@
 use pretty_jinja::{
-    config::{FormatOptions, LanguageOptions, LayoutOptions, OperatorLineBreak, TrailingComma},
-    format_expr,
+    TextRange, TextSize,
+    config::{
+        Dialect, FormatOptions, LanguageOptions, LayoutOptions, OperatorLineBreak, Quotes,
+        TrailingComma,
+    },
+    format_expr, format_expr_edits, parse_expr,
+    syntax::SyntaxKind,
+    verify_expr,
 };
 use similar_asserts::assert_eq;
 
@@ -8,65 +14,40 @@ use similar_asserts::assert_eq;
 fn single_quote_string() {
     let input = "'ab'";
     let options = Default::default();
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "'ab'");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn double_quote_string() {
     let input = "\"ab\"";
     let options = Default::default();
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "\"ab\"");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn number() {
     let input = "123_456.789";
     let options = Default::default();
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "123_456.789");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn boolean() {
     let input = "true";
     let options = Default::default();
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "true");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn list() {
     let input = "[  1 ,  2 ]";
     let options = Default::default();
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "[1, 2]");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -79,13 +60,8 @@ fn list_trailing_comma_never() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "[\n  1,\n  2\n]");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -98,13 +74,8 @@ fn list_trailing_comma_always() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "[1, 2,]");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -120,13 +91,8 @@ fn list_trailing_comma_only_multi_line() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "[\n  aaaaa,\n  bbbbb,\n]");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -139,13 +105,8 @@ fn list_prefer_single_line() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "[1, 2]");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -158,26 +119,16 @@ fn list_spacing() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "[ 1, 2 ]");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn tuple() {
     let input = "(  1 ,  2 )";
     let options = Default::default();
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "(1, 2)");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -190,13 +141,8 @@ fn tuple_trailing_comma_never() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "(\n  1,\n  2\n)");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -209,13 +155,8 @@ fn tuple_trailing_comma_always() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "(1, 2,)");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -231,13 +172,8 @@ fn tuple_trailing_comma_only_multi_line() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "(\n  aaaaa,\n  bbbbb,\n)");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -250,13 +186,8 @@ fn tuple_trailing_comma_never_for_single_component() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "(1,)");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -269,13 +200,8 @@ fn tuple_prefer_single_line() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "(1, 2)");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -288,26 +214,16 @@ fn tuple_spacing() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "( 1, 2 )");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn dict() {
     let input = "{ 'dict' : 'of' , 'key':'and','value' : 'pairs'}";
     let options = Default::default();
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "{'dict': 'of', 'key': 'and', 'value': 'pairs'}");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -320,16 +236,11 @@ fn dict_trailing_comma_never() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(
         output,
         "{\n  'dict': 'of',\n  'key': 'and',\n  'value': 'pairs'\n}"
     );
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -342,13 +253,8 @@ fn dict_trailing_comma_always() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "{'dict': 'of', 'key': 'and', 'value': 'pairs',}");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -364,16 +270,11 @@ fn dict_trailing_comma_only_multi_line() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(
         output,
         "{\n  'dict': 'of',\n  'key': 'and',\n  'value': 'pairs',\n}"
     );
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -386,13 +287,8 @@ fn dict_prefer_single_line() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "{'dict': 'of', 'key': 'and', 'value': 'pairs'}");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -405,29 +301,94 @@ fn dict_spacing() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "{ 'dict': 'of', 'key': 'and', 'value': 'pairs' }");
+}
+
+#[test]
+fn dict_colon_spacing() {
+    let input = "{'dict' : 'of' , 'key':'and'}";
+    let options = FormatOptions {
+        layout: Default::default(),
+        language: LanguageOptions {
+            dict_colon_space_before: true,
+            dict_colon_space_after: false,
+            ..Default::default()
+        },
+    };
+    let output = verify_expr(input, &options).unwrap();
+    assert_eq!(output, "{'dict' :'of', 'key' :'and'}");
+}
+
+#[test]
+fn dict_key_quote() {
+    let input = "{'dict' : 'of' , \"key\":'and', 'it\\'s': 1, key: 2}";
+    let options = FormatOptions {
+        layout: Default::default(),
+        language: LanguageOptions {
+            dict_key_quote: Quotes::Double,
+            ..Default::default()
+        },
+    };
+    let output = verify_expr(input, &options).unwrap();
+    assert_eq!(
+        output,
+        "{\"dict\": 'of', \"key\": 'and', 'it\\'s': 1, key: 2}"
+    );
+}
+
+#[test]
+fn dict_key_quote_twig_interpolation() {
+    let mut options = FormatOptions {
+        layout: Default::default(),
+        language: LanguageOptions {
+            dialect: Dialect::Twig,
+            dict_key_quote: Quotes::Double,
+            ..Default::default()
+        },
+    };
+    let output = verify_expr("{'k#{x}': 1, 'a': 2}", &options).unwrap();
+    assert_eq!(output, "{'k#{x}': 1, \"a\": 2}");
+
+    options.language.dict_key_quote = Quotes::Single;
+    let output = verify_expr("{\"k#{x}\": 1, \"a\": 2}", &options).unwrap();
+    assert_eq!(output, "{\"k#{x}\": 1, 'a': 2}");
+
+    options.language.dialect = Dialect::Jinja;
+    let output = verify_expr("{\"k#{x}\": 1}", &options).unwrap();
+    assert_eq!(output, "{'k#{x}': 1}");
+}
+
+#[test]
+fn dict_align_values() {
+    let input = "{\n'dict' : 'of' , 'key':'and','value' : 'pairs', (a): 1}";
+    let options = FormatOptions {
+        layout: Default::default(),
+        language: LanguageOptions {
+            dict_align_values: true,
+            ..Default::default()
+        },
+    };
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(
-        format_expr(&output, &options).unwrap(),
         output,
-        "format is unstable"
+        "{\n  'dict': 'of',\n  'key': 'and',\n  'value': 'pairs',\n  (a): 1,\n}"
     );
+
+    let input = "{'dict' : 'of' , 'key':'and'}";
+    let output = format_expr(input, &options).unwrap();
+    assert_eq!(output, "{'dict': 'of', 'key': 'and'}");
 }
 
 #[test]
 fn binary_expr() {
     let input = "1+2*3+4/5**6-7%8==true>false  and  not  false  or(x  in  y)";
     let options = Default::default();
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(
         output,
         "1 + 2 * 3 + 4 / 5 ** 6 - 7 % 8 == true > false and not false or (x in y)"
     );
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -443,13 +404,8 @@ fn operator_linebreak_before() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "aaaa\n  + bbbb\n  + cccc");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -465,39 +421,171 @@ fn operator_linebreak_after() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "aaaa +\n  bbbb +\n  cccc");
+}
+
+#[test]
+fn binary_expr_precedence_linebreak() {
+    let input = "aaaa and bbbbbbbb or cccccccc and dddddddd";
+    let options = FormatOptions {
+        layout: LayoutOptions {
+            print_width: 30,
+            ..Default::default()
+        },
+        language: Default::default(),
+    };
+    let output = verify_expr(input, &options).unwrap();
+    assert_eq!(output, "aaaa and bbbbbbbb or\n  cccccccc and dddddddd");
+}
+
+#[test]
+fn binary_expr_precedence_linebreak_nested() {
+    let input = "aaaaaaaaaaaa and bbbbbbbbbbbbbbbbb and cccccccccccccccc or ddddd";
+    let options = FormatOptions {
+        layout: LayoutOptions {
+            print_width: 30,
+            ..Default::default()
+        },
+        language: LanguageOptions {
+            operator_linebreak: OperatorLineBreak::Before,
+            ..Default::default()
+        },
+    };
+    let output = verify_expr(input, &options).unwrap();
+    assert_eq!(
+        output,
+        "aaaaaaaaaaaa\n    and bbbbbbbbbbbbbbbbb\n    and cccccccccccccccc\n  or ddddd"
+    );
+}
+
+#[test]
+fn remove_redundant_parens() {
+    let input = "(((a + b)) * (c) - d) - (f - g) and (x | f) is (defined) or (-1) ** (2)";
+    let options = FormatOptions {
+        layout: Default::default(),
+        language: LanguageOptions {
+            remove_redundant_parens: true,
+            ..Default::default()
+        },
+    };
+    let output = verify_expr(input, &options).unwrap();
+    assert_eq!(
+        output,
+        "(a + b) * c - d - (f - g) and x | f is defined or (-1) ** 2"
+    );
+}
+
+#[test]
+fn remove_redundant_parens_keep_non_associative() {
+    let input = "((a < b) < c) ~ ((a ** b) ** c) ~ (1).real ~ (x if (y if z else w) else v)";
+    let options = FormatOptions {
+        layout: Default::default(),
+        language: LanguageOptions {
+            remove_redundant_parens: true,
+            ..Default::default()
+        },
+    };
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(
-        format_expr(&output, &options).unwrap(),
         output,
-        "format is unstable"
+        "((a < b) < c) ~ ((a ** b) ** c) ~ (1).real ~ (x if (y if z else w) else v)"
     );
 }
 
+#[test]
+fn remove_redundant_parens_keep_grouping() {
+    let options = FormatOptions {
+        layout: Default::default(),
+        language: LanguageOptions {
+            remove_redundant_parens: true,
+            ..Default::default()
+        },
+    };
+    [
+        ("a and (b and c)", "a and (b and c)"),
+        ("a or (b or c)", "a or (b or c)"),
+        ("((x and y)) and (z and w)", "x and y and (z and w)"),
+        ("(a ~ b) ~ c", "(a ~ b) ~ c"),
+        ("a ~ (b ~ c)", "a ~ (b ~ c)"),
+    ]
+    .into_iter()
+    .for_each(|(input, expected)| {
+        assert_eq!(verify_expr(input, &options).unwrap(), expected);
+    });
+}
+
+#[test]
+fn remove_redundant_parens_twig_ternary() {
+    let input = "(a ?? b) ? (c) : (d ? e : f)";
+    let options = FormatOptions {
+        layout: Default::default(),
+        language: LanguageOptions {
+            dialect: Dialect::Twig,
+            remove_redundant_parens: true,
+            ..Default::default()
+        },
+    };
+    let output = verify_expr(input, &options).unwrap();
+    assert_eq!(output, "a ?? b ? c : (d ? e : f)");
+}
+
+#[test]
+fn clarify_mixed_and_or() {
+    let input = "a and b or (c and d) or e";
+    let options = FormatOptions {
+        layout: Default::default(),
+        language: LanguageOptions {
+            remove_redundant_parens: true,
+            clarify_mixed_and_or: true,
+            ..Default::default()
+        },
+    };
+    let output = verify_expr(input, &options).unwrap();
+    assert_eq!(output, "(a and b) or (c and d) or e");
+}
+
 #[test]
 fn test() {
     let input = "b  is  value";
     let options = Default::default();
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "b is value");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
+}
+
+#[test]
+fn negated_test_and_not_in() {
+    let input = "a  is  not  none and b  not  in  c and d is notempty";
+    let options = Default::default();
+    let output = verify_expr(input, &options).unwrap();
+    assert_eq!(output, "a is not none and b not in c and d is notempty");
+
+    [
+        Dialect::Nunjucks,
+        Dialect::Twig,
+        Dialect::Tera,
+        Dialect::Django,
+    ]
+    .into_iter()
+    .for_each(|dialect| {
+        let options = FormatOptions {
+            layout: Default::default(),
+            language: LanguageOptions {
+                dialect,
+                ..Default::default()
+            },
+        };
+        let output = verify_expr(input, &options).unwrap();
+        assert_eq!(output, "a is not none and b not in c and d is notempty");
+    });
 }
 
 #[test]
 fn test_without_parens() {
     let input = "loop.index is divisibleby 3";
     let options = Default::default();
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "loop.index is divisibleby(3)");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -510,13 +598,8 @@ fn test_without_parens_too_long() {
         },
         language: Default::default(),
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "loop.index is\n  divisibleby(\n    3,\n  )");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -529,13 +612,8 @@ fn test_without_parens_trailing_comma_always() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "loop.index is divisibleby(3,)");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -548,65 +626,40 @@ fn test_without_parens_paren_spacing() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "loop.index is divisibleby( 3 )");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn pipe() {
     let input = "a|b()  |   c()";
     let options = Default::default();
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "a | b() | c()");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn concat() {
     let input = "a~b()  ~   c()";
     let options = Default::default();
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "a ~ b() ~ c()");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn mixed_pipe_concat() {
     let input = "a~b()  |   c()  ~  d()|e()";
     let options = Default::default();
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "a ~ b() | c() ~ d() | e()");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn call() {
     let input = "post.render (1+2 , full = true)";
     let options = Default::default();
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "post.render(1 + 2, full=true)");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -619,13 +672,8 @@ fn call_trailing_comma_never() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "post.render(\n  1 + 2,\n  full=true\n)");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -638,13 +686,8 @@ fn call_trailing_comma_always() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "post.render(1 + 2, full=true,)");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -660,13 +703,8 @@ fn call_trailing_comma_only_multi_line() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "post.render(\n  1 + 2,\n  full=true,\n)");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -679,13 +717,8 @@ fn call_prefer_single_line() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "post.render(1 + 2, full=true)");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -698,68 +731,57 @@ fn call_spacing() {
             ..Default::default()
         },
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "post.render( 1 + 2, full=true )");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
+}
+
+#[test]
+fn call_kwarg_eq_spacing() {
+    let input = "post.render (1+2 , full=true)";
+    let options = FormatOptions {
+        layout: Default::default(),
+        language: LanguageOptions {
+            kwarg_eq_spacing: true,
+            ..Default::default()
+        },
+    };
+    let output = verify_expr(input, &options).unwrap();
+    assert_eq!(output, "post.render(1 + 2, full = true)");
 }
 
 #[test]
 fn get_attr() {
     let input = "a() . b";
     let options = Default::default();
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "a().b");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn get_item() {
     let input = "a() [ b*c ]";
     let options = Default::default();
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "a()[b * c]");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn mixed_access() {
     let input = "a () [ b ] . c ( ) [ d ] () .e [f]";
     let options = Default::default();
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(output, "a()[b].c()[d]().e[f]");
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn expr_if() {
     let input = "layout_template if layout_template is defined else 'default.html'";
     let options = Default::default();
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(
         output,
         "layout_template if layout_template is defined else 'default.html'"
     );
-    assert_eq!(
-        format_expr(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -772,14 +794,148 @@ fn expr_if_too_long() {
         },
         language: Default::default(),
     };
-    let output = format_expr(input, &options).unwrap();
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(
         output,
         "layout_template\nif layout_template is defined\nelse 'default.html'"
     );
+}
+
+#[test]
+fn edits() {
+    let input = "\u{feff}foo(a,b)  ";
+    let options = Default::default();
+    let edits = format_expr_edits(input, &options).unwrap();
+    assert_eq!(
+        edits,
+        [
+            (
+                TextRange::new(TextSize::new(0), TextSize::new(3)),
+                "".to_string()
+            ),
+            (TextRange::empty(TextSize::new(9)), " ".to_string()),
+            (
+                TextRange::new(TextSize::new(11), TextSize::new(13)),
+                "".to_string()
+            ),
+        ]
+    );
+
+    let edits = format_expr_edits("a  and not(b)", &Default::default()).unwrap();
+    assert_eq!(
+        edits,
+        [
+            (
+                TextRange::new(TextSize::new(1), TextSize::new(3)),
+                " ".to_string()
+            ),
+            (TextRange::empty(TextSize::new(10)), " ".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn verify_normalized_tokens() {
+    let input = "{'a': ((x)) or y and z, 'b': foo(1,2,), \"c\": (v is divisibleby 3)}";
+    let options = FormatOptions {
+        layout: Default::default(),
+        language: LanguageOptions {
+            dict_key_quote: Quotes::Double,
+            remove_redundant_parens: true,
+            clarify_mixed_and_or: true,
+            args_trailing_comma: Some(TrailingComma::Never),
+            ..Default::default()
+        },
+    };
+    let output = verify_expr(input, &options).unwrap();
     assert_eq!(
-        format_expr(&output, &options).unwrap(),
         output,
-        "format is unstable"
+        "{\"a\": x or (y and z), \"b\": foo(1, 2), \"c\": v is divisibleby(3)}"
+    );
+}
+
+#[test]
+fn bool_at_end() {
+    let input = "not (true)";
+    let options = FormatOptions {
+        layout: Default::default(),
+        language: LanguageOptions {
+            remove_redundant_parens: true,
+            ..Default::default()
+        },
+    };
+    let output = verify_expr(input, &options).unwrap();
+    assert_eq!(output, "not true");
+}
+
+#[test]
+fn floor_div() {
+    let input = "a//b";
+    let options = Default::default();
+    let output = verify_expr(input, &options).unwrap();
+    assert_eq!(output, "a // b");
+}
+
+#[test]
+fn call_arg_eq_comparison() {
+    let input = "foo(a==b,c=d==e)";
+    let options = Default::default();
+    let output = verify_expr(input, &options).unwrap();
+    assert_eq!(output, "foo(a == b, c=d == e)");
+}
+
+#[test]
+fn binary_expr_first_operand_parens_stable() {
+    let input = "a and b or c";
+    let options = FormatOptions {
+        layout: LayoutOptions {
+            print_width: 1,
+            ..Default::default()
+        },
+        language: LanguageOptions {
+            clarify_mixed_and_or: true,
+            ..Default::default()
+        },
+    };
+    let output = verify_expr(input, &options).unwrap();
+    assert_eq!(output, "(a and\n  b) or\n  c");
+
+    let input = "(foo + a) and a";
+    let options = FormatOptions {
+        layout: LayoutOptions {
+            print_width: 1,
+            ..Default::default()
+        },
+        language: LanguageOptions {
+            remove_redundant_parens: true,
+            ..Default::default()
+        },
+    };
+    let output = verify_expr(input, &options).unwrap();
+    assert_eq!(output, "foo +\n    a and\n  a");
+}
+
+#[test]
+fn unterminated_string_before_line_break() {
+    let options = Default::default();
+    assert!(format_expr("h + ')\n()", &options).is_err());
+    assert_eq!(verify_expr("a ~ 'b", &options).unwrap(), "a ~ 'b");
+}
+
+#[test]
+fn number_attr() {
+    let options = Default::default();
+    assert_eq!(verify_expr("6 .6", &options).unwrap(), "(6).6");
+    assert_eq!(verify_expr("a.5\r.5", &options).unwrap(), "(a.5).5");
+}
+
+#[test]
+fn parse_lossless() {
+    let input = "\u{feff}\u{feff} foo( a ,b= 'c' )[ 0 ] |e \n";
+    let node = parse_expr(input, Dialect::Jinja).unwrap();
+    assert_eq!(node.text(), input);
+    assert_eq!(
+        node.first_token().map(|token| token.kind()),
+        Some(SyntaxKind::BOM)
     );
 }
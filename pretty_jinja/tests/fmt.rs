@@ -0,0 +1,81 @@
+#![cfg(feature = "config_serde")]
+
+//! Snapshot tests of template files in `tests/fmt`.
+//!
+//! Each `.jinja` file is formatted and compared with `.snap` file next to it.
+//! Format options can be specified in `<name>.options.json` file.
+//! Run with `BLESS=1` environment variable to create or update snapshots.
+
+use pretty_jinja::{config::FormatOptions, verify_template};
+use similar_asserts::SimpleDiff;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+fn collect_fixtures(dir: &Path, fixtures: &mut Vec<PathBuf>) {
+    let mut entries = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            collect_fixtures(&path, fixtures);
+        } else if path.extension().is_some_and(|ext| ext == "jinja") {
+            fixtures.push(path);
+        }
+    }
+}
+
+fn load_options(input_path: &Path) -> FormatOptions {
+    let options_path = input_path.with_extension("options.json");
+    match fs::read_to_string(&options_path) {
+        Ok(json) => serde_json::from_str(&json)
+            .unwrap_or_else(|error| panic!("invalid `{}`: {error}", options_path.display())),
+        Err(_) => Default::default(),
+    }
+}
+
+#[test]
+fn fmt_snapshot() {
+    let bless = std::env::var_os("BLESS").is_some_and(|value| value != "0");
+    let mut fixtures = Vec::new();
+    collect_fixtures(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fmt"),
+        &mut fixtures,
+    );
+    assert!(!fixtures.is_empty(), "no fixtures found");
+
+    let failures = fixtures
+        .iter()
+        .filter_map(|input_path| {
+            let input = fs::read_to_string(input_path).unwrap();
+            let options = load_options(input_path);
+            let output = match verify_template(&input, &options) {
+                Ok(output) => output,
+                Err(error) => return Some(format!("{}: {error}", input_path.display())),
+            };
+            let snap_path = input_path.with_extension("snap");
+            if bless {
+                fs::write(&snap_path, &output).unwrap();
+                return None;
+            }
+            match fs::read_to_string(&snap_path) {
+                Ok(snap) if snap == output => None,
+                Ok(snap) => Some(format!(
+                    "{}: output doesn't match snapshot\n{}",
+                    input_path.display(),
+                    SimpleDiff::from_str(&snap, &output, "snapshot", "output")
+                )),
+                Err(_) => Some(format!(
+                    "{}: snapshot is missing, run with `BLESS=1` to create it",
+                    input_path.display()
+                )),
+            }
+        })
+        .collect::<Vec<_>>();
+    if !failures.is_empty() {
+        panic!("{}", failures.join("\n\n"));
+    }
+}
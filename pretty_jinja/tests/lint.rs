@@ -0,0 +1,245 @@
+use pretty_jinja::{
+    TextRange, check_template,
+    config::{Dialect, LintOptions, Severity},
+    lint::{self, Diagnostic, ParsedTag, Problem, Rule},
+    lint_template,
+};
+use similar_asserts::assert_eq;
+
+fn summarize<'a>(
+    code: &'a str,
+    diagnostics: &[Diagnostic],
+) -> Vec<(&'static str, &'a str, String)> {
+    diagnostics
+        .iter()
+        .map(|diagnostic| {
+            (
+                diagnostic.rule,
+                &code[diagnostic.range],
+                diagnostic.message.clone(),
+            )
+        })
+        .collect()
+}
+
+fn apply_edits(code: &str, edits: &[(TextRange, String)]) -> String {
+    let mut output = code.to_string();
+    edits.iter().rev().for_each(|(range, text)| {
+        output.replace_range(usize::from(range.start())..usize::from(range.end()), text);
+    });
+    output
+}
+
+#[test]
+fn loop_var_shadowing() {
+    let code = "{% for x in xs %}{% for y, x in ys %}{% endfor %}{% endfor %}\
+        {% for x in xs %}{% endfor %}{% for loop in xs %}{% endfor %}";
+    let diagnostics = lint_template(code, &Default::default()).unwrap();
+    assert_eq!(
+        summarize(code, &diagnostics),
+        [
+            (
+                "loop-var-shadowing",
+                "x",
+                "loop variable `x` shadows variable of outer loop".to_string()
+            ),
+            (
+                "loop-var-shadowing",
+                "loop",
+                "loop variable `loop` shadows the special `loop` variable".to_string()
+            ),
+        ]
+    );
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert!(diagnostics[0].fix.is_empty());
+}
+
+#[test]
+fn loop_var_shadowing_async_loops() {
+    let code = "{% asyncEach x in xs %}{% asyncAll y, x in ys %}{% endall %}{% endeach %}\
+        {% asyncEach loop in xs %}{% endeach %}{% for x in xs %}{% endfor %}";
+    let options = LintOptions {
+        dialect: Dialect::Nunjucks,
+        ..Default::default()
+    };
+    let diagnostics = lint_template(code, &options).unwrap();
+    assert_eq!(
+        summarize(code, &diagnostics),
+        [
+            (
+                "loop-var-shadowing",
+                "x",
+                "loop variable `x` shadows variable of outer loop".to_string()
+            ),
+            (
+                "loop-var-shadowing",
+                "loop",
+                "loop variable `loop` shadows the special `loop` variable".to_string()
+            ),
+        ]
+    );
+    assert_eq!(
+        usize::from(diagnostics[0].range.start()),
+        code.find("y, x").unwrap() + 3
+    );
+}
+
+#[test]
+fn unused_macro_param() {
+    let code = "{% macro m(a, b, c=d, *args) %}\
+        {{ a.b | c }}{% if x is b %}{% endif %}\
+        {% macro inner(e=a) %}{{ e }}{% endmacro %}\
+        {% endmacro %}";
+    let diagnostics = lint_template(code, &Default::default()).unwrap();
+    assert_eq!(
+        summarize(code, &diagnostics),
+        [
+            (
+                "unused-macro-param",
+                "b",
+                "macro parameter `b` is never used".to_string()
+            ),
+            (
+                "unused-macro-param",
+                "c",
+                "macro parameter `c` is never used".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn unused_macro_param_with_filter_block() {
+    let code = "{% macro m(upper, width) %}\
+        {% filter upper | center(width) %}{% endfilter %}\
+        {% endmacro %}";
+    let diagnostics = lint_template(code, &Default::default()).unwrap();
+    assert_eq!(
+        summarize(code, &diagnostics),
+        [(
+            "unused-macro-param",
+            "upper",
+            "macro parameter `upper` is never used".to_string()
+        )]
+    );
+}
+
+#[test]
+fn compare_none() {
+    let code =
+        "{{ a == none }}{% if none != b.c %}{% endif %}{{ x + 1 == None }}{{ a == b == none }}";
+    let diagnostics = lint_template(code, &Default::default()).unwrap();
+    assert_eq!(
+        summarize(code, &diagnostics),
+        [
+            (
+                "compare-none",
+                "a == none",
+                "use `is none` instead of comparing with `none`".to_string()
+            ),
+            (
+                "compare-none",
+                "none != b.c",
+                "use `is not none` instead of comparing with `none`".to_string()
+            ),
+            (
+                "compare-none",
+                "x + 1 == None",
+                "use `is none` instead of comparing with `none`".to_string()
+            ),
+            (
+                "compare-none",
+                "a == b == none",
+                "use `is none` instead of comparing with `none`".to_string()
+            ),
+        ]
+    );
+    // chained comparison can't be fixed
+    assert!(diagnostics[3].fix.is_empty());
+
+    let edits = diagnostics
+        .iter()
+        .flat_map(|diagnostic| diagnostic.fix.clone())
+        .collect::<Vec<_>>();
+    let fixed = apply_edits(code, &edits);
+    assert_eq!(
+        fixed,
+        "{{ a is none }}{% if b.c is not none %}{% endif %}{{ (x + 1) is none }}{{ a == b == none }}"
+    );
+    assert!(check_template(&fixed, Dialect::Jinja).is_empty());
+
+    // `not in` is a comparison, too
+    ["{{ a not in b == none }}", "{{ a not  in b == none }}"]
+        .into_iter()
+        .for_each(|code| {
+            let diagnostics = lint_template(code, &Default::default()).unwrap();
+            assert_eq!(diagnostics.len(), 1);
+            assert!(diagnostics[0].fix.is_empty());
+        });
+}
+
+#[test]
+fn severity() {
+    let code = "{% for loop in xs %}{{ a == none }}{% endfor %}";
+    let options = LintOptions {
+        rules: [
+            ("loop-var-shadowing".to_string(), Severity::Off),
+            ("compare-none".to_string(), Severity::Error),
+        ]
+        .into(),
+        ..Default::default()
+    };
+    let diagnostics = lint_template(code, &options).unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].rule, "compare-none");
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+}
+
+#[test]
+fn skip_invalid_tags() {
+    let code = "{{ a == }}{{ b == none }}";
+    let diagnostics = lint_template(code, &Default::default()).unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(&code[diagnostics[0].range], "b == none");
+
+    assert!(lint_template("{{ a", &Default::default()).is_err());
+}
+
+#[test]
+fn custom_rule() {
+    struct NoDebug;
+    impl Rule for NoDebug {
+        fn name(&self) -> &'static str {
+            "no-debug"
+        }
+        fn default_severity(&self) -> Severity {
+            Severity::Error
+        }
+        fn check(&self, tags: &[ParsedTag]) -> Vec<Problem> {
+            tags.iter()
+                .filter(|tag| {
+                    tag.keyword()
+                        .is_some_and(|keyword| keyword.text() == "debug")
+                })
+                .map(|tag| Problem {
+                    range: tag.range(tag.node.text_range()),
+                    message: "unexpected `debug` statement".into(),
+                    fix: vec![(tag.range(tag.node.text_range()), "".into())],
+                })
+                .collect()
+        }
+    }
+
+    let code = "{{ a }}{% debug %}";
+    let rules: Vec<Box<dyn Rule>> = vec![Box::new(NoDebug)];
+    let diagnostics = lint::lint(code, &rules, &Default::default()).unwrap();
+    assert_eq!(
+        summarize(code, &diagnostics),
+        [(
+            "no-debug",
+            " debug ",
+            "unexpected `debug` statement".to_string()
+        )]
+    );
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+}
@@ -0,0 +1,227 @@
+//! Property-based tests with randomly generated Jinja expressions.
+//!
+//! Walking syntax trees when verifying formatted code converts every raw syntax kind back,
+//! so these tests also cover `JinjaLanguage::kind_from_raw`.
+
+use pretty_jinja::{
+    config::{Dialect, FormatOptions, LanguageOptions, LayoutOptions, OperatorLineBreak},
+    format_expr, format_stmt, format_template, parse_expr, parse_stmt, verify_expr, verify_stmt,
+};
+use proptest::prelude::*;
+
+/// Placeholder of optional whitespace in generated code.
+const OPT_WS: char = '\u{1}';
+/// Placeholder of required whitespace in generated code.
+const REQ_WS: char = '\u{2}';
+
+fn leaf() -> impl Strategy<Value = String> {
+    prop_oneof![
+        prop::sample::select(vec!["a", "foo", "user_1", "loop", "_x", "none"])
+            .prop_map(String::from),
+        prop::sample::select(vec!["0", "42", "3.14", "1_000", "-7", "1e10", "2."])
+            .prop_map(String::from),
+        prop::sample::select(vec![
+            "'a'",
+            "\"b c\"",
+            "'it\\'s'",
+            "\"say \\\"hi\\\"\"",
+            "''",
+            "'{{'"
+        ])
+        .prop_map(String::from),
+        prop::sample::select(vec!["true", "false"]).prop_map(String::from),
+    ]
+}
+
+fn join(items: &[String]) -> String {
+    items.join(&format!("{OPT_WS},{OPT_WS}"))
+}
+
+/// Generate well-formed expressions following the grammar in `parser.rs`.
+fn expr() -> impl Strategy<Value = String> {
+    leaf().prop_recursive(4, 48, 4, |inner| {
+        let binary_operators = prop::sample::select(vec![
+            "+", "-", "*", "/", "//", "%", "**", "~", "==", "!=", "<", ">", "<=", ">=",
+        ]);
+        let keyword_operators = prop::sample::select(vec!["and", "or", "in"]);
+        let filters = prop::sample::select(vec!["upper", "default", "join", "e"]);
+        let tests = prop::sample::select(vec!["defined", "none", "divisibleby", "sameas"]);
+        prop_oneof![
+            (inner.clone(), binary_operators, inner.clone())
+                .prop_map(|(left, op, right)| format!("{left}{OPT_WS}{op}{OPT_WS}{right}")),
+            (inner.clone(), keyword_operators, inner.clone())
+                .prop_map(|(left, op, right)| format!("{left}{REQ_WS}{op}{REQ_WS}{right}")),
+            inner
+                .clone()
+                .prop_map(|expr| format!("(not{REQ_WS}({OPT_WS}{expr}{OPT_WS}))")),
+            inner
+                .clone()
+                .prop_map(|expr| format!("({OPT_WS}{expr}{OPT_WS})")),
+            inner
+                .clone()
+                .prop_map(|expr| format!("({expr}){OPT_WS}.{OPT_WS}attr")),
+            (inner.clone(), inner.clone())
+                .prop_map(|(expr, key)| format!("({expr}){OPT_WS}[{OPT_WS}{key}{OPT_WS}]")),
+            (
+                prop::collection::vec(inner.clone(), 0..3),
+                prop::collection::vec(inner.clone(), 0..2)
+            )
+                .prop_map(|(args, kwargs)| {
+                    let kwargs = kwargs
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, value)| format!("k{i}{OPT_WS}={OPT_WS}{value}"))
+                        .collect::<Vec<_>>();
+                    format!("foo({})", join(&[args, kwargs].concat()))
+                }),
+            (
+                inner.clone(),
+                filters,
+                prop::option::of(prop::collection::vec(inner.clone(), 0..2))
+            )
+                .prop_map(|(expr, filter, args)| match args {
+                    Some(args) => format!("({expr}){OPT_WS}|{OPT_WS}{filter}({})", join(&args)),
+                    None => format!("({expr}){OPT_WS}|{OPT_WS}{filter}"),
+                }),
+            (inner.clone(), tests, prop::option::of(leaf())).prop_map(|(expr, test, arg)| {
+                match arg {
+                    Some(arg) => format!("({expr}){REQ_WS}is{REQ_WS}{test}{REQ_WS}{arg}"),
+                    None => format!("({expr}){REQ_WS}is{REQ_WS}{test}"),
+                }
+            }),
+            prop::collection::vec(inner.clone(), 0..4)
+                .prop_map(|items| format!("[{OPT_WS}{}{OPT_WS}]", join(&items))),
+            prop::collection::vec(inner.clone(), 1..4).prop_map(|items| if items.len() == 1 {
+                format!("({OPT_WS}{}{OPT_WS},{OPT_WS})", items[0])
+            } else {
+                format!("({OPT_WS}{}{OPT_WS})", join(&items))
+            }),
+            prop::collection::vec((leaf(), inner.clone()), 0..3).prop_map(|items| {
+                let items = items
+                    .into_iter()
+                    .map(|(key, value)| format!("{key}{OPT_WS}:{OPT_WS}{value}"))
+                    .collect::<Vec<_>>();
+                format!("{{{OPT_WS}{}{OPT_WS}}}", join(&items))
+            }),
+            (inner.clone(), inner.clone(), inner).prop_map(|(then, cond, otherwise)| format!(
+                "(({then}){REQ_WS}if{REQ_WS}({cond}){REQ_WS}else{REQ_WS}({otherwise}))"
+            )),
+        ]
+    })
+}
+
+/// Replace whitespace placeholders with random whitespaces.
+fn with_whitespaces(code: String, choices: Vec<usize>) -> String {
+    const OPTIONAL: [&str; 4] = ["", " ", "  ", "\n  "];
+    const REQUIRED: [&str; 3] = [" ", "   ", "\n"];
+    let mut choices = choices.into_iter().cycle();
+    code.chars()
+        .map(|c| match c {
+            OPT_WS => OPTIONAL[choices.next().unwrap_or_default() % OPTIONAL.len()].to_string(),
+            REQ_WS => REQUIRED[choices.next().unwrap_or_default() % REQUIRED.len()].to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+fn code() -> impl Strategy<Value = String> {
+    (expr(), prop::collection::vec(0usize..12, 1..32))
+        .prop_map(|(code, choices)| with_whitespaces(code, choices))
+}
+
+fn options() -> impl Strategy<Value = FormatOptions> {
+    (
+        1usize..100,
+        any::<bool>(),
+        any::<bool>(),
+        any::<bool>(),
+        any::<bool>(),
+        any::<bool>(),
+    )
+        .prop_map(
+            |(
+                print_width,
+                operator_linebreak_after,
+                brace_spacing,
+                dict_align_values,
+                remove_redundant_parens,
+                clarify_mixed_and_or,
+            )| FormatOptions {
+                layout: LayoutOptions {
+                    print_width,
+                    ..Default::default()
+                },
+                language: LanguageOptions {
+                    operator_linebreak: if operator_linebreak_after {
+                        OperatorLineBreak::After
+                    } else {
+                        OperatorLineBreak::Before
+                    },
+                    brace_spacing,
+                    dict_align_values,
+                    remove_redundant_parens,
+                    clarify_mixed_and_or,
+                    ..Default::default()
+                },
+            },
+        )
+}
+
+proptest! {
+    #[test]
+    fn arbitrary_input_never_panics(code in "\\PC{0,64}") {
+        let options = FormatOptions::default();
+        let _ = format_expr(&code, &options);
+        let _ = format_stmt(&code, &options);
+        let _ = format_template(&code, &options);
+    }
+
+    #[test]
+    fn jinja_like_input_never_panics(code in "[a-z0-9 (){}\\[\\].,:|~*+=<>!'\"%#-]{0,64}") {
+        let options = FormatOptions::default();
+        let _ = format_expr(&code, &options);
+        let _ = format_stmt(&code, &options);
+        let _ = format_template(&code, &options);
+    }
+
+    #[test]
+    fn parse_is_lossless(code in code(), bom in any::<bool>()) {
+        let code = if bom { format!("\u{feff}{code}") } else { code };
+        if let Ok(node) = parse_expr(&code, Dialect::Jinja) {
+            prop_assert_eq!(node.text(), code.as_str());
+        }
+        let code = format!("set x = {code}");
+        if let Ok(node) = parse_stmt(&code, Dialect::Jinja) {
+            prop_assert_eq!(node.text(), code.as_str());
+        }
+        let code = format!("foo {code}, {code}");
+        if let Ok(node) = parse_stmt(&code, Dialect::Jinja) {
+            prop_assert_eq!(node.text(), code.as_str());
+        }
+    }
+
+    #[test]
+    fn jinja_like_parse_is_lossless(code in "[a-z0-9 (){}\\[\\].,:|~*+=<>!'\"%#-]{0,64}") {
+        if let Ok(node) = parse_expr(&code, Dialect::Jinja) {
+            prop_assert_eq!(node.text(), code.as_str());
+        }
+        if let Ok(node) = parse_stmt(&code, Dialect::Jinja) {
+            prop_assert_eq!(node.text(), code.as_str());
+        }
+    }
+
+    #[test]
+    fn expr_round_trip(code in code(), options in options()) {
+        if let Err(error) = verify_expr(&code, &options) {
+            prop_assert!(false, "{error}\ninput:\n{code}");
+        }
+    }
+
+    #[test]
+    fn stmt_round_trip(code in code(), options in options()) {
+        let code = format!("set x = {code}");
+        if let Err(error) = verify_stmt(&code, &options) {
+            prop_assert!(false, "{error}\ninput:\n{code}");
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use pretty_jinja::{
-    config::{FormatOptions, LanguageOptions, LayoutOptions, TrailingComma},
-    format_stmt,
+    config::{Dialect, FormatOptions, LanguageOptions, LayoutOptions, TrailingComma},
+    format_stmt, format_stmt_edits, parse_stmt, verify_stmt,
 };
 use similar_asserts::assert_eq;
 
@@ -8,84 +8,54 @@ use similar_asserts::assert_eq;
 fn for_simple() {
     let input = "for  key,value  in  my_dict|dictsort";
     let options = Default::default();
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(output, "for key, value in my_dict | dictsort");
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn for_if() {
     let input = "for  user   in users if   not user . hidden";
     let options = Default::default();
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(output, "for user in users if not user.hidden");
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn for_recursive() {
     let input = "for   item   in   sitemap  recursive";
     let options = Default::default();
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(output, "for item in sitemap recursive");
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn if_simple() {
     let input = "if   loop . previtem  is   defined and value>loop.previtem";
     let options = Default::default();
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(
         output,
         "if loop.previtem is defined and value > loop.previtem"
     );
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn elif() {
     let input = "elif   loop . previtem  is   defined and value>loop.previtem";
     let options = Default::default();
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(
         output,
         "elif loop.previtem is defined and value > loop.previtem"
     );
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn macro_simple() {
     let input = "macro  input( name ,  value = '' , type= 'text'  , size =20, )";
     let options = Default::default();
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(output, "macro input(name, value='', type='text', size=20)");
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -98,16 +68,11 @@ fn macro_trailing_comma_never() {
             ..Default::default()
         },
     };
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(
         output,
         "macro input(\n  name,\n  value='',\n  type='text',\n  size=20\n)"
     );
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -120,13 +85,8 @@ fn macro_trailing_comma_always() {
             ..Default::default()
         },
     };
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(output, "macro input(name, value='', type='text', size=20,)");
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -142,16 +102,11 @@ fn macro_trailing_comma_only_multi_line() {
             ..Default::default()
         },
     };
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(
         output,
         "macro input(\n  name,\n  value='',\n  type='text',\n  size=20,\n)"
     );
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -164,13 +119,8 @@ fn macro_prefer_single_line() {
             ..Default::default()
         },
     };
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(output, "macro input(name, value='', type='text', size=20)");
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -183,29 +133,19 @@ fn macro_spacing() {
             ..Default::default()
         },
     };
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(
         output,
         "macro input( name, value='', type='text', size=20 )"
     );
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn call() {
     let input = "call  post (1+2 , full = true)";
     let options = Default::default();
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(output, "call post(1 + 2, full=true)");
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -218,13 +158,8 @@ fn call_trailing_comma_never() {
             ..Default::default()
         },
     };
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(output, "call post(\n  1 + 2,\n  full=true\n)");
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -237,13 +172,8 @@ fn call_trailing_comma_always() {
             ..Default::default()
         },
     };
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(output, "call post(1 + 2, full=true,)");
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -259,13 +189,8 @@ fn call_trailing_comma_only_multi_line() {
             ..Default::default()
         },
     };
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(output, "call post(\n  1 + 2,\n  full=true,\n)");
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -278,13 +203,8 @@ fn call_prefer_single_line() {
             ..Default::default()
         },
     };
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(output, "call post(1 + 2, full=true)");
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -297,13 +217,8 @@ fn call_spacing() {
             ..Default::default()
         },
     };
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(output, "call post( 1 + 2, full=true )");
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -316,16 +231,11 @@ fn call_params_trailing_comma_never() {
             ..Default::default()
         },
     };
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(
         output,
         "call(\n  name,\n  value,\n  type,\n  size\n) dump_users(list_of_user)"
     );
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -338,16 +248,11 @@ fn call_params_trailing_comma_always() {
             ..Default::default()
         },
     };
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(
         output,
         "call(name, value, type, size,) dump_users(list_of_user)"
     );
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -363,16 +268,11 @@ fn call_params_trailing_comma_only_multi_line() {
             ..Default::default()
         },
     };
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(
         output,
         "call(\n  name,\n  value,\n  type,\n  size,\n) dump_users(list_of_user)"
     );
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -385,16 +285,11 @@ fn call_params_prefer_single_line() {
             ..Default::default()
         },
     };
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(
         output,
         "call(name, value, type, size) dump_users(list_of_user)"
     );
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -407,29 +302,27 @@ fn call_params_spacing() {
             ..Default::default()
         },
     };
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(
         output,
         "call( name, value, type, size ) dump_users(list_of_user)"
     );
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn filter() {
     let input = " filter  upper ";
     let options = Default::default();
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(output, "filter upper");
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
+}
+
+#[test]
+fn filter_chain() {
+    let input = "filter  center(80)|upper |  trim";
+    let options = Default::default();
+    let output = verify_stmt(input, &options).unwrap();
+    assert_eq!(output, "filter center(80) | upper | trim");
 }
 
 #[test]
@@ -442,13 +335,8 @@ fn filter_trailing_comma_never() {
             ..Default::default()
         },
     };
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(output, "filter post(\n  1 + 2,\n  full=true\n)");
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -461,13 +349,8 @@ fn filter_trailing_comma_always() {
             ..Default::default()
         },
     };
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(output, "filter post(1 + 2, full=true,)");
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -483,13 +366,8 @@ fn filter_trailing_comma_only_multi_line() {
             ..Default::default()
         },
     };
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(output, "filter post(\n  1 + 2,\n  full=true,\n)");
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -502,13 +380,8 @@ fn filter_prefer_single_line() {
             ..Default::default()
         },
     };
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(output, "filter post(1 + 2, full=true)");
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
@@ -521,98 +394,140 @@ fn filter_spacing() {
             ..Default::default()
         },
     };
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(output, "filter post( 1 + 2, full=true )");
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn set() {
     let input = "set  key,value=[('index.html','Index'),('about.html','About')]";
     let options = Default::default();
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(
         output,
         "set key, value = [('index.html', 'Index'), ('about.html', 'About')]"
     );
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn set_block() {
     let input = "set  key,value";
     let options = Default::default();
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(output, "set key, value");
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn set_with_filters() {
     let input = "set  key,value|wordwrap|upper";
     let options = Default::default();
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(output, "set key, value | wordwrap | upper");
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn include() {
     let input = "include   \"sidebar.html\"   ignore     missing    without    context";
     let options = Default::default();
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(
         output,
         "include \"sidebar.html\" ignore missing without context"
     );
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn import_from() {
     let input = "from   'forms.html'   import  input   as   input_field ,textarea";
     let options = Default::default();
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(
         output,
         "from 'forms.html' import input as input_field, textarea"
     );
-    assert_eq!(
-        format_stmt(&output, &options).unwrap(),
-        output,
-        "format is unstable"
-    );
 }
 
 #[test]
 fn import_with_context() {
     let input = "from   'forms.html'   import  input   with  context";
     let options = Default::default();
-    let output = format_stmt(input, &options).unwrap();
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(output, "from 'forms.html' import input with context");
+}
+
+#[test]
+fn macro_varargs() {
+    let input = "macro  input( name , *  args, ** kwargs )";
+    let options = Default::default();
+    let output = verify_stmt(input, &options).unwrap();
+    assert_eq!(output, "macro input(name, *args, **kwargs)");
+}
+
+#[test]
+fn macro_params_align_defaults() {
+    let input = "macro  input(\nname ,  value = '' , type= 'text'  , size =20, *args)";
+    let options = FormatOptions {
+        layout: Default::default(),
+        language: LanguageOptions {
+            params_align_defaults: true,
+            ..Default::default()
+        },
+    };
+    let output = verify_stmt(input, &options).unwrap();
     assert_eq!(
-        format_stmt(&output, &options).unwrap(),
         output,
-        "format is unstable"
+        "macro input(\n  name,\n  value='',\n  type ='text',\n  size =20,\n  *args,\n)"
     );
+
+    let input = "macro  input(name ,  value = '' , type= 'text')";
+    let output = format_stmt(input, &options).unwrap();
+    assert_eq!(output, "macro input(name, value='', type='text')");
+}
+
+#[test]
+fn macro_kwarg_eq_spacing() {
+    let input = "macro  input(\nname ,  value='' , type= 'text'  , size =20)";
+    let options = FormatOptions {
+        layout: Default::default(),
+        language: LanguageOptions {
+            kwarg_eq_spacing: true,
+            params_align_defaults: true,
+            ..Default::default()
+        },
+    };
+    let output = verify_stmt(input, &options).unwrap();
+    assert_eq!(
+        output,
+        "macro input(\n  name,\n  value = '',\n  type  = 'text',\n  size  = 20,\n)"
+    );
+}
+
+#[test]
+fn edits() {
+    let input = "for  x in xs";
+    let options = Default::default();
+    let edits = format_stmt_edits(input, &options).unwrap();
+    assert_eq!(edits.len(), 1);
+    assert_eq!(usize::from(edits[0].0.start()), 3);
+    assert_eq!(usize::from(edits[0].0.end()), 5);
+    assert_eq!(edits[0].1, " ");
+
+    assert!(
+        format_stmt_edits("for x in xs", &options)
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn parse_lossless() {
+    let inputs = [
+        "\u{feff}for  k , v in  items if  k ",
+        "unknown a ,b , c",
+        "macro m( a , b= 1 , *args , **kwargs )",
+        "call (x , y) m( 1 )",
+    ];
+    for input in inputs {
+        assert_eq!(parse_stmt(input, Dialect::Jinja).unwrap().text(), input);
+    }
 }
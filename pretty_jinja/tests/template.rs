@@ -0,0 +1,212 @@
+use pretty_jinja::{
+    TextRange, TextSize, check_template, config::Dialect, config::FormatOptions, format_template,
+    format_template_edits, format_template_range, verify_template,
+};
+use similar_asserts::assert_eq;
+
+#[test]
+fn expr_and_stmt() {
+    let input =
+        "<ul>\n{%for  user   in users%}\n  <li>{{user . name|title}}</li>\n{%endfor%}\n</ul>\n";
+    let options = Default::default();
+    let output = verify_template(input, &options).unwrap();
+    assert_eq!(
+        output,
+        "<ul>\n{% for user in users %}\n  <li>{{ user.name | title }}</li>\n{% endfor %}\n</ul>\n"
+    );
+}
+
+#[test]
+fn whitespace_control() {
+    let input = "{%- if  a -%}{{- a+1 +}}{%+ endif +%}";
+    let options = Default::default();
+    let output = verify_template(input, &options).unwrap();
+    assert_eq!(output, "{%- if a -%}{{- a + 1 +}}{%+ endif +%}");
+}
+
+#[test]
+fn comment_and_raw() {
+    let input = "{#  {{a+1}}  #}{% raw %}{{a+1}}{%if%}{% endraw %}{{{'a':{'b':'}}'}}}}";
+    let options = Default::default();
+    let output = verify_template(input, &options).unwrap();
+    assert_eq!(
+        output,
+        "{#  {{a+1}}  #}{% raw %}{{a+1}}{%if%}{% endraw %}{{ {'a': {'b': '}}'}} }}"
+    );
+}
+
+#[test]
+fn twig_verbatim_and_whitespace_control() {
+    let input = "{%~ verbatim -%}{{a ??}}{% endverbatim ~%}{{~a??b~}}{% raw %}{{a+1}}";
+    let mut options = FormatOptions::default();
+    options.language.dialect = Dialect::Twig;
+    let output = verify_template(input, &options).unwrap();
+    assert_eq!(
+        output,
+        "{%~ verbatim -%}{{a ??}}{% endverbatim ~%}{{~ a ?? b ~}}{% raw %}{{ a + 1 }}"
+    );
+    assert!(format_template(input, &Default::default()).is_err());
+}
+
+#[test]
+fn multiline_tag_indent() {
+    let input = "<div>\n  {{ foo(aaaaaaaaaa, bbbbbbbbbb, cccccccccc) }}\n</div>";
+    let options = FormatOptions {
+        layout: pretty_jinja::config::LayoutOptions {
+            print_width: 30,
+            ..Default::default()
+        },
+        language: Default::default(),
+    };
+    let output = verify_template(input, &options).unwrap();
+    assert_eq!(
+        output,
+        "<div>\n  {{ foo(\n    aaaaaaaaaa,\n    bbbbbbbbbb,\n    cccccccccc,\n  ) }}\n</div>"
+    );
+}
+
+#[test]
+fn unclosed_tag() {
+    let input = "<p>\n  {{ a </p>";
+    let error = format_template(input, &Default::default()).unwrap_err();
+    assert_eq!(error, "unclosed tag at line 2, column 3");
+}
+
+#[test]
+fn django_named_verbatim() {
+    let mut options = FormatOptions::default();
+    options.language.dialect = Dialect::Django;
+    let input = "{% verbatim a %}{{a+1}}{% endverbatim %}{% endverbatim b %}\n";
+    let error = format_template(input, &options).unwrap_err();
+    assert_eq!(error, "unclosed raw block at line 1, column 1");
+}
+
+fn apply_edits(code: &str, edits: &[(TextRange, String)]) -> String {
+    let mut output = code.to_string();
+    edits.iter().rev().for_each(|(range, text)| {
+        output.replace_range(usize::from(range.start())..usize::from(range.end()), text);
+    });
+    output
+}
+
+#[test]
+fn range() {
+    let input = "{{a+1}}\n{%if  b%}{{c+1}}{%endif%}\n{{d+1}}";
+    let options = Default::default();
+    let edits = format_template_range(
+        input,
+        TextRange::new(TextSize::new(10), TextSize::new(20)),
+        &options,
+    )
+    .unwrap();
+    assert_eq!(
+        edits,
+        [
+            (TextRange::empty(TextSize::new(10)), " ".to_string()),
+            (
+                TextRange::new(TextSize::new(12), TextSize::new(14)),
+                " ".to_string()
+            ),
+            (TextRange::empty(TextSize::new(15)), " ".to_string()),
+            (TextRange::empty(TextSize::new(19)), " ".to_string()),
+            (TextRange::empty(TextSize::new(20)), " ".to_string()),
+            (TextRange::empty(TextSize::new(21)), " ".to_string()),
+            (TextRange::empty(TextSize::new(22)), " ".to_string()),
+        ]
+    );
+    assert_eq!(
+        apply_edits(input, &edits),
+        "{{a+1}}\n{% if b %}{{ c + 1 }}{%endif%}\n{{d+1}}"
+    );
+}
+
+#[test]
+fn range_cursor() {
+    let input = "{{a+1}} {{ b }}";
+    let options = Default::default();
+    let edits = format_template_range(input, TextRange::empty(TextSize::new(3)), &options).unwrap();
+    assert_eq!(apply_edits(input, &edits), "{{ a + 1 }} {{ b }}");
+
+    let edits =
+        format_template_range(input, TextRange::empty(TextSize::new(10)), &options).unwrap();
+    assert!(edits.is_empty());
+}
+
+#[test]
+fn edits() {
+    let input = "<ul>\n  {%for x in xs%}\n    <li>{{x|upper}}</li>\n  {%endfor%}\n</ul>\n";
+    let options = Default::default();
+    let edits = format_template_edits(input, &options).unwrap();
+    assert!(edits.iter().all(|(_, text)| text.trim().is_empty()));
+    assert_eq!(
+        apply_edits(input, &edits),
+        format_template(input, &options).unwrap()
+    );
+
+    let output = apply_edits(input, &edits);
+    assert!(format_template_edits(&output, &options).unwrap().is_empty());
+}
+
+#[test]
+fn check() {
+    let input = "{{ a + }}\n{% for x in %}{{ b }}{{ a b }}";
+    let errors = check_template(input, Dialect::Jinja)
+        .into_iter()
+        .map(|(range, message)| (&input[range], message))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        [
+            ("+", "unexpected `+`".to_string()),
+            ("in", "unexpected `in`".to_string()),
+            ("b", "unexpected `b`".to_string()),
+        ]
+    );
+
+    let errors = check_template("{{ a }}\n{% if b", Dialect::Jinja);
+    assert_eq!(
+        errors,
+        [(
+            TextRange::new(TextSize::new(8), TextSize::new(15)),
+            "unclosed tag".to_string()
+        )]
+    );
+    assert!(check_template("{{ a }}{# b #}", Dialect::Jinja).is_empty());
+
+    let input = "{{ a === r/b/g }}";
+    assert_eq!(check_template(input, Dialect::Jinja).len(), 1);
+    assert!(check_template(input, Dialect::Nunjucks).is_empty());
+}
+
+#[test]
+fn width_after_previous_tag_on_same_line() {
+    let input = "{{a+b+c}} {{ foo(aaaa, bbbb) }}";
+    let options = FormatOptions {
+        layout: pretty_jinja::config::LayoutOptions {
+            print_width: 32,
+            ..Default::default()
+        },
+        language: Default::default(),
+    };
+    let output = verify_template(input, &options).unwrap();
+    assert_eq!(output, "{{ a + b + c }} {{ foo(\n  aaaa,\n  bbbb,\n) }}");
+    let edits = format_template_edits(input, &options).unwrap();
+    assert_eq!(apply_edits(input, &edits), output);
+}
+
+#[test]
+fn indent_only_spaces_and_tabs() {
+    let input = "\u{b}{{ foo(aaaaaaaaaa, bbbbbbbbbb, cccccccccc) }}";
+    let options = FormatOptions {
+        layout: pretty_jinja::config::LayoutOptions {
+            print_width: 30,
+            ..Default::default()
+        },
+        language: Default::default(),
+    };
+    let output = verify_template(input, &options).unwrap();
+    assert_eq!(
+        output,
+        "\u{b}{{ foo(\n  aaaaaaaaaa,\n  bbbbbbbbbb,\n  cccccccccc,\n) }}"
+    );
+}